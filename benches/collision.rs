@@ -0,0 +1,114 @@
+// Times collision tests and hard drops on the bitboard against the cell-by-cell
+// tests it replaced, which collected the piece's cells into a Vec for every test.
+// Run with `cargo bench`.
+use quads::board::bitboard::Bitboard;
+use quads::board::cursor::piece::Shape;
+use quads::board::cursor::Cursor;
+use quads::board::position::Position;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const NUM_COLS: usize = 10;
+const NUM_ROWS: usize = 40;
+const NUM_ROUNDS: u32 = 50_000;
+
+fn cells_fit(rows: &[Vec<bool>], cursor: &Cursor) -> bool {
+    cursor.get_point_positions().iter().all(|pos| {
+        usize::try_from(pos.y)
+            .ok()
+            .zip(usize::try_from(pos.x).ok())
+            .and_then(|(y, x)| rows.get(y)?.get(x))
+            .is_some_and(|&locked| !locked)
+    })
+}
+
+fn cells_drop_y(rows: &[Vec<bool>], cursor: &Cursor) -> i32 {
+    let mut y = cursor.position.y;
+    while cells_fit(
+        rows,
+        &cursor.offset_copy(Position {
+            x: cursor.position.x,
+            y: y + 1,
+        }),
+    ) {
+        y += 1;
+    }
+    y
+}
+
+// A ragged stack of garbage along the bottom half of the board.
+fn locked_cells() -> Vec<Position> {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let (num_cols, num_rows) = (NUM_COLS as i32, NUM_ROWS as i32);
+    (num_rows / 2..num_rows)
+        .flat_map(|y| {
+            (0..num_cols)
+                .filter(move |x| (x * 7 + y * 3) % num_cols != 0)
+                .map(move |x| Position { x, y })
+        })
+        .collect()
+}
+
+// Runs `f` on every cursor, round after round, and prints the time per call.
+fn time(name: &str, cursors: &[Cursor], mut f: impl FnMut(&Cursor)) -> Duration {
+    let start = Instant::now();
+    for _ in 0..NUM_ROUNDS {
+        for cursor in cursors {
+            f(black_box(cursor));
+        }
+    }
+    let elapsed = start.elapsed();
+    #[allow(clippy::cast_precision_loss)]
+    let num_calls = f64::from(NUM_ROUNDS) * cursors.len() as f64;
+    println!(
+        "{name:<24} {:>8.1} ns/call",
+        elapsed.as_secs_f64() * 1e9 / num_calls
+    );
+    elapsed
+}
+
+fn main() {
+    let mut bitboard = Bitboard::new(NUM_COLS, NUM_ROWS);
+    let mut rows = vec![vec![false; NUM_COLS]; NUM_ROWS];
+    for pos in locked_cells() {
+        bitboard.set(pos);
+        #[allow(clippy::cast_sign_loss)]
+        let (x, y) = (pos.x as usize, pos.y as usize);
+        rows[y][x] = true;
+    }
+    let cursors: Vec<Cursor> = [Shape::I, Shape::T, Shape::S, Shape::L]
+        .into_iter()
+        .flat_map(|shape| {
+            (2..8).map(move |x| Cursor::new(shape, Position { x, y: 1 }).rotate_cw_copy())
+        })
+        .collect();
+    let bitboard_fits = time("fits (bitboard)", &cursors, |cursor| {
+        black_box(bitboard.fits(cursor.piece.mask(), cursor.position));
+    });
+    let cells_fits = time("fits (cells)", &cursors, |cursor| {
+        black_box(cells_fit(&rows, cursor));
+    });
+    println!(
+        "fits speedup             {:>8.1}x",
+        cells_fits.as_secs_f64() / bitboard_fits.as_secs_f64()
+    );
+
+    let bitboard_drop = time("drop_y (bitboard)", &cursors, |cursor| {
+        black_box(bitboard.drop_y(cursor.piece.mask(), cursor.position));
+    });
+    let cells_drop = time("drop_y (cells)", &cursors, |cursor| {
+        black_box(cells_drop_y(&rows, cursor));
+    });
+    println!(
+        "drop_y speedup           {:>8.1}x",
+        cells_drop.as_secs_f64() / bitboard_drop.as_secs_f64()
+    );
+
+    // Both agree on every cursor, so the timings compare like with like.
+    for cursor in &cursors {
+        assert_eq!(
+            bitboard.drop_y(cursor.piece.mask(), cursor.position),
+            cells_drop_y(&rows, cursor)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use super::board::rotation_system::{Rotation180, RotationSystem};
+use super::board::BoardConfig;
+use super::game_mode::ModeConfig;
+use super::ruleset::{parse_num_next_pieces, RulesetOverrides, SoftDropSpeed};
+use super::theme::{parse_font_scale, parse_opacity, Theme};
+
+pub struct Args {
+    pub board_config: BoardConfig,
+    pub ruleset_overrides: RulesetOverrides,
+    pub mode_config: ModeConfig,
+    pub theme: Theme,
+    pub font_scale: f32,
+    pub fullscreen: bool,
+    pub integer_scaling: bool,
+}
+
+// Board options (`--cols N`, `--rows N`, `--hidden-rows N`), ruleset overrides
+// (`--rotation no-kicks|srs|nrs`, `--rotate-180 off|no-kicks|srs-plus`,
+// `--soft-drop FACTOR|instant`, `--soft-drop-lock on|off`, `--next N`,
+// `--irs-ihs on|off`) and display options (`--theme NAME|PATH`, `--patterns`,
+// `--ghost-opacity X`, `--font-scale X`, `--fullscreen`, `--integer-scaling`) may
+// appear anywhere; the remaining arguments select the game mode.
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Args {
+    let mut board_config = BoardConfig::default();
+    let mut ruleset_overrides = RulesetOverrides::default();
+    let mut theme = Theme::default();
+    let mut patterns = false;
+    let mut opt_ghost_opacity = None;
+    let mut font_scale = 1.0;
+    let mut fullscreen = false;
+    let mut integer_scaling = false;
+    let mut mode_args = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--theme" => {
+                match args.next() {
+                    Some(value) => theme = Theme::from_arg(&value),
+                    None => eprintln!("Missing value for '{arg}', using the default"),
+                }
+                continue;
+            }
+            "--patterns" => {
+                patterns = true;
+                continue;
+            }
+            "--ghost-opacity" => {
+                opt_ghost_opacity = parse_option_value(&arg, args.next(), parse_opacity);
+                continue;
+            }
+            "--font-scale" => {
+                font_scale =
+                    parse_option_value(&arg, args.next(), parse_font_scale).unwrap_or(font_scale);
+                continue;
+            }
+            "--fullscreen" => {
+                fullscreen = true;
+                continue;
+            }
+            "--integer-scaling" => {
+                integer_scaling = true;
+                continue;
+            }
+            "--rotation" => {
+                ruleset_overrides.opt_rotation_system =
+                    parse_option_value(&arg, args.next(), RotationSystem::from_arg);
+                continue;
+            }
+            "--rotate-180" => {
+                ruleset_overrides.opt_rotation_180 =
+                    parse_option_value(&arg, args.next(), Rotation180::from_arg);
+                continue;
+            }
+            "--soft-drop" => {
+                ruleset_overrides.opt_soft_drop_speed =
+                    parse_option_value(&arg, args.next(), SoftDropSpeed::from_arg);
+                continue;
+            }
+            "--soft-drop-lock" => {
+                ruleset_overrides.opt_soft_drop_locks_on_ground =
+                    parse_option_value(&arg, args.next(), parse_on_off);
+                continue;
+            }
+            "--irs-ihs" => {
+                ruleset_overrides.opt_irs_ihs_enabled =
+                    parse_option_value(&arg, args.next(), parse_on_off);
+                continue;
+            }
+            "--next" => {
+                ruleset_overrides.opt_num_next_pieces =
+                    parse_option_value(&arg, args.next(), parse_num_next_pieces);
+                continue;
+            }
+            _ => (),
+        }
+        let opt_dimension = match arg.as_str() {
+            "--cols" => Some(&mut board_config.num_cols),
+            "--rows" => Some(&mut board_config.num_visible_rows),
+            "--hidden-rows" => Some(&mut board_config.num_hidden_rows),
+            _ => None,
+        };
+        match opt_dimension {
+            Some(dimension) => match args.next().map(|value| value.parse::<usize>()) {
+                Some(Ok(value)) => *dimension = value,
+                _ => eprintln!("Missing or invalid value for '{arg}', using the default"),
+            },
+            None => mode_args.push(arg),
+        }
+    }
+    // These apply on top of whichever theme was chosen.
+    theme.patterns |= patterns;
+    if let Some(ghost_opacity) = opt_ghost_opacity {
+        theme.ghost_opacity = ghost_opacity;
+    }
+    Args {
+        board_config,
+        ruleset_overrides,
+        mode_config: ModeConfig::from_args(mode_args.into_iter()),
+        theme,
+        font_scale,
+        fullscreen,
+        integer_scaling,
+    }
+}
+
+fn parse_option_value<T>(
+    arg: &str,
+    opt_value: Option<String>,
+    parse: fn(&str) -> Option<T>,
+) -> Option<T> {
+    let opt_parsed = opt_value.as_deref().and_then(parse);
+    if opt_parsed.is_none() {
+        eprintln!("Missing or invalid value for '{arg}', using the default");
+    }
+    opt_parsed
+}
+
+fn parse_on_off(arg: &str) -> Option<bool> {
+    match arg {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,44 @@
+use quads::args::parse_args;
+use quads::draw::end_screen_lines;
+use quads::engine::{initialize_game, reset_or_quit_game_when_apt};
+use quads::game_mode::GameResult;
+use quads::tui::{Terminal, TerminalKeyboard};
+use std::io;
+use std::time::{Duration, Instant};
+
+const FRAME_DURATION: Duration = Duration::from_micros(16_667);
+
+// Plays in the terminal, taking the same arguments as the window frontend. Of the
+// display options, only the theme's colors and patterns apply.
+fn main() -> io::Result<()> {
+    let args = parse_args(std::env::args().skip(1));
+    let mut gp = initialize_game(args.mode_config, args.board_config, args.ruleset_overrides);
+
+    let mut terminal = Terminal::new()?;
+    let mut keyboard = TerminalKeyboard::new(terminal.reports_key_releases());
+
+    while !gp.exit_game {
+        let keys = keyboard.read_until(Instant::now() + FRAME_DURATION)?;
+        if gp.game_over {
+            let summary_lines = gp.mode.summary_lines();
+            let headline = match gp.mode.result() {
+                GameResult::Completed => format!("{} Complete!", gp.mode.name()),
+                result => result.headline(),
+            };
+            let lines = end_screen_lines(
+                &gp.board,
+                &headline,
+                &summary_lines,
+                gp.leaderboard_result(),
+            );
+            terminal.draw_lines(&lines)?;
+            reset_or_quit_game_when_apt(&mut gp, &keys);
+        } else {
+            gp.step(&keys, Instant::now());
+            // Cleared rows vanish at once; there is no animation to wait for.
+            gp.board.set_row_removal_animation_is_pending_to_false();
+            terminal.draw_board(&gp.board, &gp.hud(), &args.theme)?;
+        }
+    }
+    Ok(())
+}
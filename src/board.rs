@@ -1,17 +1,25 @@
-pub mod cell;
+pub mod bitboard;
+mod cell;
 pub mod cursor;
 pub mod position;
+pub(crate) mod randomizer;
+pub(crate) mod rotation_system;
+pub(crate) mod view;
 
+use super::ruleset::{Ruleset, SpawnPosition};
 use super::tetromino_move::TetrominoMove;
 use super::user_move::UserMove;
-use cell::Cell;
-use cursor::piece::Shape;
+use bitboard::Bitboard;
+use cell::{Cell, LockInfo, PieceId, Tick};
+use cursor::piece::{RotationDir, Shape};
 use cursor::Cursor;
 use macroquad::prelude::rand;
 use position::Position;
+use rotation_system::Candidates;
 use std::collections::VecDeque;
 use std::fs::{metadata, File};
 use std::io::{self, BufRead, Write};
+use view::ViewRow;
 
 pub type Row = Vec<Cell>;
 
@@ -20,30 +28,112 @@ pub struct Board {
     num_total_rows: usize,
     num_cols: usize,
     rows: Vec<Row>,
-    cursor_start_position: Position,
+    // Mirrors which cells of `rows` are locked, for fast collision tests.
+    bitboard: Bitboard,
     cursor_queue: VecDeque<Cursor>,
     cursor: Cursor,
+    opt_held_shape: Option<Shape>,
+    // Only one hold is allowed per piece.
+    hold_used: bool,
     next_shape_candidates: Vec<Shape>,
+    ruleset: Ruleset,
+    level: usize,
+    num_pieces_locked: PieceId,
+    num_rows_cleared: usize,
+    // The number of locks in a row that cleared rows, after the first.
+    opt_combo: Option<usize>,
+    // Whether the last clear was difficult, and whether it followed another one.
+    last_clear_was_difficult: bool,
+    back_to_back: bool,
+    tick: Tick,
     score: i32,
     high_score: i32,
+    high_score_filename: Option<&'static str>,
     row_removal_animation_is_pending: bool,
     rows_just_before_removal_of_full_rows: Vec<Row>,
 }
 
-const NUM_HIDDEN_ROWS_ABOVE_VISIBLE_ROWS: usize = 4;
-type ToppedOut = bool;
-type NumRowsClearedThisUpdate = usize;
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoardConfig {
+    pub num_cols: usize,
+    pub num_visible_rows: usize,
+    // Rows above the visible field that pieces spawn into.
+    pub num_hidden_rows: usize,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            num_cols: 10,
+            num_visible_rows: 20,
+            num_hidden_rows: 4,
+        }
+    }
+}
+
+impl BoardConfig {
+    // Every piece must fit side by side within the columns and in the hidden rows.
+    pub const MIN_NUM_COLS: usize = 4;
+    // Each row of the bitboard is a single 64-bit mask.
+    pub const MAX_NUM_COLS: usize = bitboard::MAX_NUM_COLS;
+    pub const MIN_NUM_VISIBLE_ROWS: usize = 4;
+    pub const MIN_NUM_HIDDEN_ROWS: usize = 2;
+
+    fn clamped(self) -> Self {
+        BoardConfig {
+            num_cols: self.num_cols.clamp(Self::MIN_NUM_COLS, Self::MAX_NUM_COLS),
+            num_visible_rows: self.num_visible_rows.max(Self::MIN_NUM_VISIBLE_ROWS),
+            num_hidden_rows: self.num_hidden_rows.max(Self::MIN_NUM_HIDDEN_ROWS),
+        }
+    }
+}
+
+// The rule by which the stack topped out, ending the game.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum TopOut {
+    // The next piece spawned overlapping the stack.
+    BlockOut,
+    // A piece locked entirely above the visible field.
+    LockOut,
+    // A piece locked partly above the visible field.
+    PartialLockOut,
+    // Rising garbage pushed the stack or the piece off the top of the board.
+    PushOut,
+}
+
+impl TopOut {
+    pub fn name(self) -> &'static str {
+        match self {
+            TopOut::BlockOut => "Block Out",
+            TopOut::LockOut => "Lock Out",
+            TopOut::PartialLockOut => "Partial Lock Out",
+            TopOut::PushOut => "Push Out",
+        }
+    }
+}
+
+pub type NumRowsClearedThisUpdate = usize;
+
+#[derive(Copy, Clone, Debug)]
+pub struct DockingOutcome {
+    pub opt_top_out: Option<TopOut>,
+    pub num_rows_cleared: NumRowsClearedThisUpdate,
+    // Number of rows between the floor and the lowest cell of the locked piece.
+    pub lock_height: usize,
+}
 
 impl Board {
     // Construction
-    pub fn new() -> Self {
+    pub fn new(ruleset: Ruleset, config: BoardConfig) -> Self {
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
         rand::srand(macroquad::miniquad::date::now() as _);
-        let num_visible_rows: usize = 20;
-        let num_total_rows = num_visible_rows + NUM_HIDDEN_ROWS_ABOVE_VISIBLE_ROWS;
-        let num_cols: usize = 10;
+        let config = config.clamped();
+        let num_visible_rows = config.num_visible_rows;
+        let num_total_rows = num_visible_rows + config.num_hidden_rows;
+        let num_cols = config.num_cols;
 
-        let mut rows = vec![vec![Cell::new(); num_cols]; num_total_rows];
+        let rows = vec![vec![Cell::new(); num_cols]; num_total_rows];
 
         let next_shape_candidates = vec![
             Shape::O,
@@ -55,190 +145,391 @@ impl Board {
             Shape::L,
         ];
 
-        let cursor_start_position = Position {
-            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-            x: (num_cols as i32 - 1) / 2,
-            y: 0,
-        };
-
+        // The piece in play comes from the queue too, and there is always a next
+        // piece to take its place, even when none is shown.
+        let num_queued_cursors = ruleset.num_next_pieces.max(1) + 1;
+        let num_hidden_rows = config.num_hidden_rows;
         let mut cursor_queue = VecDeque::new();
-        cursor_queue.push_back(Cursor::from_random_shape_in_list(
-            &next_shape_candidates,
-            cursor_start_position,
-        ));
-        cursor_queue.push_back(Cursor::from_random_shape_in_list(
-            &next_shape_candidates,
-            cursor_start_position,
-        ));
+        let mut opt_prev_shape = None;
+        for _ in 0..num_queued_cursors {
+            let shape = ruleset
+                .randomizer
+                .next_shape(&next_shape_candidates, opt_prev_shape);
+            cursor_queue.push_back(spawn_cursor(shape, &ruleset, num_cols, num_hidden_rows));
+            opt_prev_shape = Some(shape);
+        }
 
         let cursor = cursor_queue.pop_front().unwrap();
-        set_state_of_cells_at_cursor(&cursor, &mut rows, cell::State::Cursor);
-
         let rows_just_before_removal_of_full_rows = rows.clone();
 
-        Board {
+        let mut board = Board {
             num_visible_rows,
             num_total_rows,
             num_cols,
             rows,
-            cursor_start_position,
+            bitboard: Bitboard::new(num_cols, num_total_rows),
             cursor,
             cursor_queue,
+            opt_held_shape: None,
+            hold_used: false,
             next_shape_candidates,
+            ruleset,
+            level: 0,
+            num_pieces_locked: 0,
+            num_rows_cleared: 0,
+            opt_combo: None,
+            last_clear_was_difficult: false,
+            back_to_back: false,
+            tick: 0,
             score: 0,
-            high_score: read_high_score_from_file(),
+            high_score: 0,
+            high_score_filename: None,
             row_removal_animation_is_pending: false,
             rows_just_before_removal_of_full_rows,
-        }
+        };
+        board.apply_spawn_drop();
+        board
     }
 }
 
 impl Board {
+    // Returns the outcome of docking when the move locked the piece into the stack.
     #[must_use]
-    pub fn update(
-        &mut self,
-        tetromino_move: TetrominoMove,
-    ) -> (ToppedOut, NumRowsClearedThisUpdate) {
-        let mut topped_out: ToppedOut = false;
-        let mut rows_cleared_this_update = 0;
+    pub fn update(&mut self, tetromino_move: TetrominoMove) -> Option<DockingOutcome> {
+        let mut opt_docking_outcome = None;
+
+        if tetromino_move == TetrominoMove::UM(UserMove::HardDown)
+            && !self.ruleset.hard_drop_enabled
+        {
+            return opt_docking_outcome;
+        }
+
+        if matches!(
+            tetromino_move,
+            TetrominoMove::UM(UserMove::SonicDrop | UserMove::FirmDrop)
+        ) && !self.ruleset.sonic_drop_enabled
+        {
+            return opt_docking_outcome;
+        }
+
+        if tetromino_move == TetrominoMove::UM(UserMove::Hold) {
+            self.hold();
+            return opt_docking_outcome;
+        }
 
         let hard_drop_y = self.calc_hard_drop_y(&self.cursor);
 
-        let new_cursor =
-            calc_new_cursor_pos_and_orientation(&self.cursor, tetromino_move, hard_drop_y);
+        let mut new_cursor_candidates =
+            calc_new_cursor_candidates(&self.cursor, tetromino_move, hard_drop_y, self.ruleset);
 
-        if self.fits_on_board(&new_cursor) {
-            self.set_cell_states_at_cursor(cell::State::Empty);
+        let opt_new_cursor = new_cursor_candidates.find(|candidate| self.fits_on_board(candidate));
+
+        if let Some(new_cursor) = opt_new_cursor {
+            #[allow(clippy::cast_sign_loss)]
+            let num_rows_moved_down =
+                (new_cursor.position.y - self.cursor.position.y).max(0) as usize;
             self.cursor = new_cursor;
-            self.set_cell_states_at_cursor(cell::State::Cursor);
             match tetromino_move {
-                TetrominoMove::UM(UserMove::SoftDown) => self.increment_score_by(1),
+                TetrominoMove::UM(UserMove::SoftDown) => {
+                    self.increment_score_by(self.ruleset.scoring.soft_drop_points());
+                }
                 TetrominoMove::UM(UserMove::HardDown) => {
-                    self.increment_score_by(12);
-                    let (new_topped_out, rows_cleared) = self.run_docking_sequence();
-                    topped_out = new_topped_out;
-                    rows_cleared_this_update = rows_cleared;
+                    self.increment_score_by(
+                        self.ruleset.scoring.hard_drop_points(num_rows_moved_down),
+                    );
+                    opt_docking_outcome = Some(self.run_docking_sequence());
                 }
                 _ => (),
             }
         } else if tetromino_move == TetrominoMove::AutoDown
-            || tetromino_move == TetrominoMove::UM(UserMove::SoftDown)
+            || (tetromino_move == TetrominoMove::UM(UserMove::SoftDown)
+                && self.ruleset.soft_drop_locks_on_ground)
         {
-            let (new_topped_out, rows_cleared) = self.run_docking_sequence();
-            topped_out = new_topped_out;
-            rows_cleared_this_update = rows_cleared;
+            opt_docking_outcome = Some(self.run_docking_sequence());
         }
 
-        (topped_out, rows_cleared_this_update)
+        opt_docking_outcome
     }
 
-    fn calc_hard_drop_y(&self, cursor: &Cursor) -> i32 {
-        let mut hard_drop_y = cursor.position.y;
-        let mut point_positions = cursor.get_point_positions();
+    // True when the cursor cannot move down any further.
+    pub fn is_grounded(&self) -> bool {
+        let one_row_down = Position {
+            x: self.cursor.position.x,
+            y: self.cursor.position.y + 1,
+        };
+        !self.bitboard.fits(self.cursor.piece.mask(), one_row_down)
+    }
 
-        while self.cursor_cells_fit_on_board(&point_positions) {
-            increment_ys(&mut point_positions);
-            hard_drop_y += 1;
-        }
-        hard_drop_y - 1
+    fn calc_hard_drop_y(&self, cursor: &Cursor) -> i32 {
+        self.bitboard.drop_y(cursor.piece.mask(), cursor.position)
     }
 
-    fn run_docking_sequence(&mut self) -> (ToppedOut, NumRowsClearedThisUpdate) {
+    fn run_docking_sequence(&mut self) -> DockingOutcome {
+        let lock_height = self.lock_height_of_cursor();
+        let opt_lock_out = self.check_lock_out();
         self.dock_cursor_to_stack();
         self.rows_just_before_removal_of_full_rows
             .clone_from(&self.rows);
         let num_rows_cleared = self.remove_full_rows_from_stack();
-        let topped_out = self.stack_height() >= self.num_visible_rows;
-        self.drop_new_piece();
-        (topped_out, num_rows_cleared)
+        let opt_block_out = self.drop_new_piece();
+        DockingOutcome {
+            opt_top_out: opt_lock_out.or(opt_block_out),
+            num_rows_cleared,
+            lock_height,
+        }
+    }
+
+    fn lock_height_of_cursor(&self) -> usize {
+        let lowest_y = self
+            .cursor
+            .get_point_positions()
+            .iter()
+            .map(|pos| pos.y)
+            .max()
+            .unwrap_or(0);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let num_total_rows = self.num_total_rows as i32;
+        #[allow(clippy::cast_sign_loss)]
+        return (num_total_rows - 1 - lowest_y).max(0) as usize;
     }
 
     fn fits_on_board(&self, cursor: &Cursor) -> bool {
-        let point_positions = cursor.get_point_positions();
-        self.cursor_cells_fit_on_board(&point_positions)
+        self.bitboard.fits(cursor.piece.mask(), cursor.position)
     }
 
-    fn cursor_cells_fit_on_board(&self, cursor_cell_positions: &[Position]) -> bool {
-        if self.any_is_out_of_bounds(cursor_cell_positions) {
-            return false;
+    fn dock_cursor_to_stack(&mut self) {
+        let lock_info = LockInfo {
+            shape: self.cursor.piece.shape(),
+            piece_id: self.num_pieces_locked,
+            lock_tick: self.tick,
+        };
+        self.num_pieces_locked += 1;
+        #[allow(clippy::cast_sign_loss)]
+        for local_point in self.cursor.piece.get_local_points() {
+            let pos = Position {
+                x: self.cursor.position.x + local_point.x,
+                y: self.cursor.position.y + local_point.y,
+            };
+            self.rows[pos.y as usize][pos.x as usize] = Cell::stack(lock_info);
+            self.bitboard.set(pos);
+        }
+    }
+
+    fn remove_full_rows_from_stack(&mut self) -> NumRowsClearedThisUpdate {
+        let rows = &mut self.rows;
+        let num_removed_rows = self.bitboard.remove_full_rows(|y| {
+            // Move the full row to the top and empty it in place.
+            rows[..=y].rotate_right(1);
+            rows[0].fill(Cell::new());
+        });
+        self.update_clear_streaks(num_removed_rows);
+        if num_removed_rows > 0 {
+            self.increment_score_by(
+                self.ruleset
+                    .scoring
+                    .line_clear_points(num_removed_rows, self.level),
+            );
+            self.row_removal_animation_is_pending = true;
         }
-        self.all_not_occupied_by_stack(cursor_cell_positions)
+        num_removed_rows
     }
 
-    fn any_is_out_of_bounds(&self, positions: &[Position]) -> bool {
-        positions.iter().any(|&pos| self.is_out_of_bounds(pos))
+    // A lock that clears nothing ends a combo but not a back-to-back streak, which
+    // only an easier clear ends. Only four row clears count as difficult, as
+    // T-spins aren't detected.
+    fn update_clear_streaks(&mut self, num_removed_rows: usize) {
+        if num_removed_rows == 0 {
+            self.opt_combo = None;
+            return;
+        }
+        self.num_rows_cleared += num_removed_rows;
+        self.opt_combo = Some(self.opt_combo.map_or(0, |combo| combo + 1));
+        let difficult = num_removed_rows >= 4;
+        self.back_to_back = difficult && self.last_clear_was_difficult;
+        self.last_clear_was_difficult = difficult;
     }
 
-    fn is_out_of_bounds(&self, pos: Position) -> bool {
+    // Checks the cursor, which is about to lock, against the ruleset's lock out rules.
+    fn check_lock_out(&self) -> Option<TopOut> {
+        let rules = self.ruleset.top_out_rules;
         #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-        let (w, h) = (self.num_cols as i32, self.num_total_rows as i32);
-        !(0..w).contains(&pos.x) || !(0..h).contains(&pos.y)
+        let num_hidden_rows = self.num_hidden_rows() as i32;
+        let point_positions = self.cursor.get_point_positions();
+        let is_above_visible_field = |pos: &Position| pos.y < num_hidden_rows;
+        if rules.lock_out && point_positions.iter().all(is_above_visible_field) {
+            Some(TopOut::LockOut)
+        } else if rules.partial_lock_out && point_positions.iter().any(is_above_visible_field) {
+            Some(TopOut::PartialLockOut)
+        } else {
+            None
+        }
     }
 
-    fn all_not_occupied_by_stack(&self, positions: &[Position]) -> bool {
-        #[allow(clippy::cast_sign_loss)]
-        return positions
-            .iter()
-            .all(|&pos| self.rows[pos.y as usize][pos.x as usize].state != cell::State::Stack);
+    // Reports a block out when the ruleset checks for it and the new piece
+    // overlaps the stack.
+    fn drop_new_piece(&mut self) -> Option<TopOut> {
+        self.cursor = self.pop_next_cursor();
+        self.hold_used = false;
+        if self.ruleset.top_out_rules.block_out && !self.fits_on_board(&self.cursor) {
+            return Some(TopOut::BlockOut);
+        }
+        self.apply_spawn_drop();
+        None
     }
 
-    fn dock_cursor_to_stack(&mut self) {
-        self.set_cell_states_at_cursor(cell::State::Stack);
+    fn pop_next_cursor(&mut self) -> Cursor {
+        let opt_prev_shape = self.cursor_queue.back().map(|cursor| cursor.piece.shape());
+        let shape = self
+            .ruleset
+            .randomizer
+            .next_shape(&self.next_shape_candidates, opt_prev_shape);
+        self.cursor_queue.push_back(spawn_cursor(
+            shape,
+            &self.ruleset,
+            self.num_cols,
+            self.num_hidden_rows(),
+        ));
+        self.cursor_queue.pop_front().unwrap()
     }
 
-    fn remove_full_rows_from_stack(&mut self) -> NumRowsClearedThisUpdate {
-        let orig_num_rows = self.rows.len();
-        self.rows.retain(is_not_a_full_row);
-        let num_removed_rows = orig_num_rows - self.rows.len();
-        if num_removed_rows > 0 {
-            let new_rows = vec![vec![Cell::new(); self.num_cols]; num_removed_rows];
-            self.rows.splice(0..0, new_rows);
-            self.increment_score_by(get_points(num_removed_rows));
-            self.row_removal_animation_is_pending = true;
+    // Swaps the piece in play for the held piece, or for the next piece when nothing
+    // is held yet. The swap is refused when the incoming piece would not fit.
+    fn hold(&mut self) {
+        if !self.ruleset.hold_enabled || self.hold_used {
+            return;
         }
-        num_removed_rows
+        let incoming_cursor = match self.opt_held_shape {
+            Some(shape) => {
+                spawn_cursor(shape, &self.ruleset, self.num_cols, self.num_hidden_rows())
+            }
+            None => *self.cursor_queue.front().unwrap(),
+        };
+        if !self.fits_on_board(&incoming_cursor) {
+            return;
+        }
+        if self.opt_held_shape.is_none() {
+            self.pop_next_cursor();
+        }
+
+        self.opt_held_shape = Some(self.cursor.piece.shape());
+        self.cursor = incoming_cursor;
+        self.apply_spawn_drop();
+        self.hold_used = true;
     }
 
-    fn stack_height(&self) -> usize {
-        let opt_index_highest_stack_row = self.rows.iter().position(contains_any_stack_cell);
+    // Guideline pieces drop one row as soon as they spawn if nothing is in the way.
+    fn apply_spawn_drop(&mut self) {
+        if self.ruleset.spawn_position != SpawnPosition::Guideline {
+            return;
+        }
+        let one_row_down = self.cursor.offset_copy(Position {
+            x: self.cursor.position.x,
+            y: self.cursor.position.y + 1,
+        });
+        if self.fits_on_board(&one_row_down) {
+            self.cursor = one_row_down;
+        }
+    }
+
+    // Pushes the stack and the cursor up by one row per entry, with a single hole
+    // at the given column. Reports a push out when locked cells are pushed off the
+    // top of the board or the cursor has nowhere left to go.
+    pub fn insert_garbage_rows(&mut self, hole_cols: &[usize]) -> Option<TopOut> {
+        let num_new_rows = hole_cols.len().min(self.num_total_rows);
+        let full_row_mask = self.bitboard.full_row_mask();
+        let pushed_out_stack = self.bitboard.push_rows_from_bottom(
+            hole_cols[..num_new_rows]
+                .iter()
+                .map(|&hole_col| full_row_mask & !(1 << hole_col)),
+        );
+        for &hole_col in &hole_cols[..num_new_rows] {
+            // Recycle the top row as the new bottom row.
+            self.rows.rotate_left(1);
+            let row = self.rows.last_mut().unwrap();
+            row.fill(Cell::garbage());
+            row[hole_col] = Cell::new();
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let raised_cursor = self.cursor.offset_copy(Position {
+            x: self.cursor.position.x,
+            y: self.cursor.position.y - num_new_rows as i32,
+        });
+        let mut cursor_pushed_out = false;
+        if self.fits_on_board(&raised_cursor) {
+            self.cursor = raised_cursor;
+        } else if !self.fits_on_board(&self.cursor) {
+            cursor_pushed_out = true;
+        }
 
-        if let Some(index_highest_stack_row) = opt_index_highest_stack_row {
-            self.num_total_rows - index_highest_stack_row
+        if pushed_out_stack || cursor_pushed_out {
+            Some(TopOut::PushOut)
         } else {
-            0
+            None
         }
     }
 
-    fn drop_new_piece(&mut self) {
-        self.cursor_queue
-            .push_back(Cursor::from_random_shape_in_list(
-                &self.next_shape_candidates,
-                self.cursor_start_position,
-            ));
-        self.cursor = self.cursor_queue.pop_front().unwrap();
-        self.set_cell_states_at_cursor(cell::State::Cursor);
+    pub fn num_garbage_rows(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|row| contains_any_garbage_cell(row))
+            .count()
+    }
+
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    // Called once per frame of play; stack cells record the tick they locked on.
+    pub fn advance_tick(&mut self) {
+        self.tick += 1;
     }
 
-    fn set_cell_states_at_cursor(&mut self, state: cell::State) {
-        set_state_of_cells_at_cursor(&self.cursor, &mut self.rows, state);
+    // The level used to scale line clear points, for rulesets that do so.
+    pub fn set_level(&mut self, level: usize) {
+        self.level = level;
+    }
+
+    pub fn num_total_rows(&self) -> usize {
+        self.num_total_rows
     }
 
     pub fn num_cols(&self) -> usize {
         self.num_cols
     }
 
+    pub fn num_visible_rows(&self) -> usize {
+        self.num_visible_rows
+    }
+
     pub fn visible_rows(&self) -> &[Row] {
-        &self.rows[NUM_HIDDEN_ROWS_ABOVE_VISIBLE_ROWS..]
+        &self.rows[self.num_hidden_rows()..]
     }
 
     pub fn visible_rows_just_before_removal_of_full_rows(&self) -> &[Row] {
-        &self.rows_just_before_removal_of_full_rows[NUM_HIDDEN_ROWS_ABOVE_VISIBLE_ROWS..]
+        &self.rows_just_before_removal_of_full_rows[self.num_hidden_rows()..]
     }
 
     pub fn num_hidden_rows(&self) -> usize {
         self.num_total_rows - self.num_visible_rows
     }
 
+    // The cells of the piece in play, which are not part of the grid until it locks.
+    pub fn active_piece_positions(&self) -> Vec<Position> {
+        self.cursor.get_point_positions()
+    }
+
+    // The visible rows with the piece in play laid over the locked cells,
+    // ready for a renderer to draw.
+    pub fn visible_view(&self) -> Vec<ViewRow> {
+        view::compose(
+            self.visible_rows(),
+            self.cursor.piece.shape(),
+            &self.active_piece_positions(),
+            self.num_hidden_rows(),
+        )
+    }
+
     pub fn ghost_cursor_positions(&self) -> Vec<Position> {
         let hard_drop_y = self.calc_hard_drop_y(&self.cursor);
         let ghost_cursor = self.cursor.offset_copy(Position {
@@ -248,8 +539,14 @@ impl Board {
         ghost_cursor.get_point_positions()
     }
 
+    // The piece that follows the one in play; there always is one.
     pub fn next_piece(&self) -> &cursor::piece::Piece {
-        &self.cursor_queue.front().unwrap().piece
+        self.next_pieces(1).next().unwrap()
+    }
+
+    // Up to `n` of the pieces that follow the one in play, in the order they come.
+    pub fn next_pieces(&self, n: usize) -> impl Iterator<Item = &cursor::piece::Piece> {
+        self.cursor_queue.iter().take(n).map(|cursor| &cursor.piece)
     }
 }
 
@@ -258,7 +555,30 @@ impl Board {
     fn increment_score_by(&mut self, increment_amount: i32) {
         self.score += increment_amount;
         self.high_score = self.score.max(self.high_score);
-        write_high_score_to_file(self.high_score);
+    }
+
+    // Starts from the high score kept in the file, and keeps it there from now on.
+    pub fn load_high_score(&mut self, filename: &'static str) {
+        self.high_score = read_high_score_from_file(filename);
+        self.high_score_filename = Some(filename);
+    }
+
+    // Called once the game is over. Writes the score to the high score file when
+    // it is the high score.
+    pub fn save_high_score(&self) {
+        if let Some(filename) = self.high_score_filename {
+            if self.score > 0 && self.score == self.high_score {
+                write_high_score_to_file(filename, self.high_score);
+            }
+        }
+    }
+
+    pub fn held_piece(&self) -> Option<cursor::piece::Piece> {
+        self.opt_held_shape.map(cursor::piece::Piece::new)
+    }
+
+    pub fn hold_used(&self) -> bool {
+        self.hold_used
     }
 
     pub fn score(&self) -> i32 {
@@ -269,6 +589,22 @@ impl Board {
         self.high_score
     }
 
+    pub fn num_pieces_locked(&self) -> u64 {
+        self.num_pieces_locked
+    }
+
+    pub fn num_rows_cleared(&self) -> usize {
+        self.num_rows_cleared
+    }
+
+    pub fn opt_combo(&self) -> Option<usize> {
+        self.opt_combo
+    }
+
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
     pub fn row_removal_animation_is_pending(&self) -> bool {
         self.row_removal_animation_is_pending
     }
@@ -278,11 +614,9 @@ impl Board {
     }
 }
 
-const FILENAME_HIGH_SCORE: &str = "high_score.txt";
-
-fn read_high_score_from_file() -> i32 {
-    if metadata(FILENAME_HIGH_SCORE).is_ok() {
-        if let Ok(file) = File::open(FILENAME_HIGH_SCORE) {
+fn read_high_score_from_file(filename: &str) -> i32 {
+    if metadata(filename).is_ok() {
+        if let Ok(file) = File::open(filename) {
             let reader = io::BufReader::new(file);
 
             // Read the first line from the file
@@ -299,9 +633,9 @@ fn read_high_score_from_file() -> i32 {
     0
 }
 
-fn write_high_score_to_file(high_score: i32) {
+fn write_high_score_to_file(filename: &str, high_score: i32) {
     // Attempt to open the file in write-only mode, creating it if it doesn't exist
-    let mut file = match File::create(FILENAME_HIGH_SCORE) {
+    let mut file = match File::create(filename) {
         Ok(file) => file,
         Err(e) => {
             eprintln!("Failed to create or open file: {e}");
@@ -312,68 +646,447 @@ fn write_high_score_to_file(high_score: i32) {
     // Attempt to write the number to the file
     if let Err(e) = writeln!(file, "{high_score}") {
         eprintln!("Failed to write to file: {e}");
-    } else {
-        println!("Number {high_score} written to {FILENAME_HIGH_SCORE}");
     }
 }
 
-fn increment_ys(positions: &mut [Position]) {
-    positions.iter_mut().for_each(|pos| pos.y += 1);
-}
-
 #[rustfmt::skip]
-fn calc_new_cursor_pos_and_orientation(curr: &Cursor, tetromino_move: TetrominoMove, hard_drop_y: i32) -> Cursor {
+fn calc_new_cursor_candidates(curr: &Cursor, tetromino_move: TetrominoMove, hard_drop_y: i32, ruleset: Ruleset) -> Candidates {
     let curr_pos = curr.position;
     let cur_x = curr_pos.x;
     let cur_y = curr_pos.y;
     match tetromino_move {
         TetrominoMove::AutoDown | TetrominoMove::UM(UserMove::SoftDown) => {
-            curr.offset_copy(Position {x: cur_x, y: cur_y + 1,})
+            Candidates::only(curr.offset_copy(Position {x: cur_x, y: cur_y + 1,}))
         }
-        TetrominoMove::UM(UserMove::HardDown) => {
-            curr.offset_copy(Position {x: cur_x, y: hard_drop_y,})
+        TetrominoMove::UM(UserMove::HardDown | UserMove::SonicDrop | UserMove::FirmDrop) => {
+            Candidates::only(curr.offset_copy(Position {x: cur_x, y: hard_drop_y,}))
         }
         TetrominoMove::UM(UserMove::Left) => {
-            curr.offset_copy(Position {x: cur_x - 1, y: cur_y,})
+            Candidates::only(curr.offset_copy(Position {x: cur_x - 1, y: cur_y,}))
         }
         TetrominoMove::UM(UserMove::Right) => {
-            curr.offset_copy(Position {x: cur_x + 1,y: cur_y,})
+            Candidates::only(curr.offset_copy(Position {x: cur_x + 1,y: cur_y,}))
+        }
+        TetrominoMove::UM(UserMove::RotateCW) => {
+            ruleset.rotation_system.rotation_candidates(curr, &RotationDir::Clockwise)
         }
-        TetrominoMove::UM(UserMove::RotateCW) => curr.rotate_cw_copy(),
-        TetrominoMove::UM(UserMove::RotateCCW) => curr.rotate_ccw_copy(),
+        TetrominoMove::UM(UserMove::RotateCCW) => {
+            ruleset.rotation_system.rotation_candidates(curr, &RotationDir::Counterclockwise)
+        }
+        TetrominoMove::UM(UserMove::Rotate180) => {
+            ruleset.rotation_system.rotation_180_candidates(curr, ruleset.rotation_180)
+        }
+        // Holding swaps the piece rather than moving it; see `Board::hold`.
+        TetrominoMove::UM(UserMove::Hold) => Candidates::none(),
     }
 }
 
-fn set_state_of_cells_at_cursor(cursor: &Cursor, rows: &mut [Row], state: cell::State) {
-    #[allow(clippy::cast_sign_loss)]
-    cursor
-        .get_point_positions()
-        .iter()
-        .for_each(|position| set_state(&mut rows[position.y as usize][position.x as usize], state));
-}
+// Places a new piece in the rotation system's spawn orientation, horizontally
+// centered and leaning left when it can't be centered exactly.
+fn spawn_cursor(
+    shape: Shape,
+    ruleset: &Ruleset,
+    num_cols: usize,
+    num_hidden_rows: usize,
+) -> Cursor {
+    let spawn_rotation_state = ruleset.rotation_system.spawn_rotation_state(shape);
+    let mut cursor = Cursor::new(shape, Position { x: 0, y: 0 });
+    while cursor.piece.rotation_state() != spawn_rotation_state {
+        cursor = cursor.rotate_cw_copy();
+    }
 
-fn set_state(cell: &mut Cell, state: cell::State) {
-    cell.state = state;
+    let point_positions = cursor.get_point_positions();
+    let min_x = point_positions.iter().map(|pos| pos.x).min().unwrap_or(0);
+    let max_x = point_positions.iter().map(|pos| pos.x).max().unwrap_or(0);
+    let min_y = point_positions.iter().map(|pos| pos.y).min().unwrap_or(0);
+    let max_y = point_positions.iter().map(|pos| pos.y).max().unwrap_or(0);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let (num_cols, num_hidden_rows) = (num_cols as i32, num_hidden_rows as i32);
+    let left_col = (num_cols - (max_x - min_x + 1)) / 2;
+    let y = match ruleset.spawn_position {
+        // The lowest cells go in the row just above the visible field.
+        SpawnPosition::Guideline => num_hidden_rows - 1 - max_y,
+        SpawnPosition::TopOfField => num_hidden_rows - min_y,
+    };
+    cursor.offset_copy(Position {
+        x: left_col - min_x,
+        y,
+    })
 }
 
-fn is_not_a_full_row(row: &Row) -> bool {
-    !is_a_full_row(row)
+fn contains_any_garbage_cell(row: &Row) -> bool {
+    row.iter().any(|&cell| cell.state == cell::State::Garbage)
 }
 
-fn is_a_full_row(row: &Row) -> bool {
-    row.iter().all(|&cell| cell.state == cell::State::Stack)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ruleset::TopOutRules;
+    use bitboard::PieceMask;
+    use cursor::piece::SHAPES;
 
-fn contains_any_stack_cell(row: &Row) -> bool {
-    row.iter().any(|&cell| cell.state == cell::State::Stack)
-}
+    // Replaces the piece in play with a freshly spawned one of the given shape.
+    fn set_active_piece(board: &mut Board, shape: Shape) {
+        board.cursor = spawn_cursor(
+            shape,
+            &board.ruleset,
+            board.num_cols,
+            board.num_hidden_rows(),
+        );
+        board.apply_spawn_drop();
+    }
+
+    fn fill_row_with_garbage(board: &mut Board, y: usize) {
+        board.rows[y].fill(Cell::garbage());
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        for x in 0..board.num_cols as i32 {
+            board.bitboard.set(Position { x, y: y as i32 });
+        }
+    }
+
+    fn assert_rows_match_bitboard(board: &Board) {
+        let single_cell = PieceMask::from_points(&[Position { x: 0, y: 0 }; 4]);
+        for (y, row) in board.rows.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let pos = Position {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                let is_filled = cell.state != cell::State::Empty;
+                assert_eq!(
+                    !board.bitboard.fits(&single_cell, pos),
+                    is_filled,
+                    "cell {pos:?}"
+                );
+            }
+        }
+    }
+
+    fn moved_up(positions: &[Position], num_rows: i32) -> Vec<Position> {
+        positions
+            .iter()
+            .map(|pos| Position {
+                x: pos.x,
+                y: pos.y - num_rows,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stack_cells_record_the_piece_and_tick_they_locked_on() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        let hard_drop = TetrominoMove::UM(UserMove::HardDown);
+        board.advance_tick();
+        board.advance_tick();
+        let first_shape = board.cursor.piece.shape();
+        assert!(board.update(hard_drop).is_some());
+        board.advance_tick();
+        let second_shape = board.cursor.piece.shape();
+        assert!(board.update(hard_drop).is_some());
+
+        // Two pieces can't fill a row of ten, so all eight cells are still there.
+        let mut lock_infos: Vec<LockInfo> = board
+            .rows
+            .iter()
+            .flatten()
+            .filter_map(|cell| cell.opt_lock_info)
+            .collect();
+        lock_infos.sort_by_key(|lock_info| lock_info.piece_id);
+        let first = LockInfo {
+            shape: first_shape,
+            piece_id: 0,
+            lock_tick: 2,
+        };
+        let second = LockInfo {
+            shape: second_shape,
+            piece_id: 1,
+            lock_tick: 3,
+        };
+        assert_eq!(lock_infos, [[first; 4], [second; 4]].concat());
+    }
+
+    #[test]
+    fn keeps_the_high_score_up_to_date_with_the_score() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        board.high_score = 10;
+        board.increment_score_by(4);
+        assert_eq!(board.high_score(), 10);
+        board.increment_score_by(8);
+        assert_eq!(board.high_score(), 12);
+    }
+
+    #[test]
+    fn garbage_rows_rise_from_the_bottom_with_one_hole_each() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        assert_eq!(board.insert_garbage_rows(&[3, 7]), None);
+
+        assert_eq!(board.num_garbage_rows(), 2);
+        let bottom = board.num_total_rows - 1;
+        for (y, hole_col) in [(bottom - 1, 3), (bottom, 7)] {
+            for (x, cell) in board.rows[y].iter().enumerate() {
+                let expected_state = if x == hole_col {
+                    cell::State::Empty
+                } else {
+                    cell::State::Garbage
+                };
+                assert!(cell.state == expected_state, "cell ({x}, {y})");
+            }
+        }
+        assert_rows_match_bitboard(&board);
+    }
+
+    #[test]
+    fn garbage_rows_push_the_stack_up() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        set_active_piece(&mut board, Shape::O);
+        let locked_positions = board.ghost_cursor_positions();
+        assert!(board
+            .update(TetrominoMove::UM(UserMove::HardDown))
+            .is_some());
+
+        assert_eq!(board.insert_garbage_rows(&[0]), None);
+
+        #[allow(clippy::cast_sign_loss)]
+        for pos in moved_up(&locked_positions, 1) {
+            assert!(board.rows[pos.y as usize][pos.x as usize].state == cell::State::Stack);
+        }
+        assert_rows_match_bitboard(&board);
+    }
+
+    #[test]
+    fn garbage_rows_push_the_active_piece_up() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        set_active_piece(&mut board, Shape::T);
+        let positions = board.active_piece_positions();
+
+        assert_eq!(board.insert_garbage_rows(&[0, 0]), None);
+
+        assert_eq!(board.active_piece_positions(), moved_up(&positions, 2));
+    }
+
+    #[test]
+    fn garbage_that_pushes_the_stack_off_the_top_pushes_out() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        fill_row_with_garbage(&mut board, 0);
+
+        assert_eq!(board.insert_garbage_rows(&[0]), Some(TopOut::PushOut));
+        assert_rows_match_bitboard(&board);
+    }
+
+    #[test]
+    fn garbage_that_leaves_the_active_piece_nowhere_to_go_pushes_out() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        set_active_piece(&mut board, Shape::O);
+        // Filling the visible field leaves the stack clear of the top of the board.
+        let hole_cols = vec![0; board.num_visible_rows];
+
+        assert_eq!(board.insert_garbage_rows(&hole_cols), Some(TopOut::PushOut));
+        assert_rows_match_bitboard(&board);
+    }
+
+    fn board_with_top_out_rules(top_out_rules: TopOutRules) -> Board {
+        let ruleset = Ruleset {
+            top_out_rules,
+            ..Ruleset::modern()
+        };
+        Board::new(ruleset, BoardConfig::default())
+    }
+
+    const NO_TOP_OUT_RULES: TopOutRules = TopOutRules {
+        block_out: false,
+        lock_out: false,
+        partial_lock_out: false,
+    };
+
+    // A new piece spawning into a full row just above the visible field.
+    fn check_block_out(top_out_rules: TopOutRules) -> Option<TopOut> {
+        let mut board = board_with_top_out_rules(top_out_rules);
+        let y = board.num_hidden_rows() - 1;
+        fill_row_with_garbage(&mut board, y);
+        board.drop_new_piece()
+    }
+
+    // An O piece about to lock in the two rows just above the visible field.
+    fn check_lock_out_above_the_field(top_out_rules: TopOutRules) -> Option<TopOut> {
+        let mut board = board_with_top_out_rules(top_out_rules);
+        board.cursor = spawn_cursor(
+            Shape::O,
+            &board.ruleset,
+            board.num_cols,
+            board.num_hidden_rows(),
+        );
+        board.check_lock_out()
+    }
+
+    // An O piece about to lock across the top of the visible field.
+    fn check_lock_out_across_the_top(top_out_rules: TopOutRules) -> Option<TopOut> {
+        let mut board = board_with_top_out_rules(top_out_rules);
+        set_active_piece(&mut board, Shape::O);
+        board.check_lock_out()
+    }
+
+    #[test]
+    fn block_out_ends_the_game_only_when_checked() {
+        let block_out = TopOutRules {
+            block_out: true,
+            ..NO_TOP_OUT_RULES
+        };
+        assert_eq!(check_block_out(block_out), Some(TopOut::BlockOut));
+        assert_eq!(check_block_out(NO_TOP_OUT_RULES), None);
+    }
+
+    #[test]
+    fn lock_out_ends_the_game_only_when_checked() {
+        let lock_out = TopOutRules {
+            lock_out: true,
+            ..NO_TOP_OUT_RULES
+        };
+        assert_eq!(
+            check_lock_out_above_the_field(lock_out),
+            Some(TopOut::LockOut)
+        );
+        assert_eq!(check_lock_out_across_the_top(lock_out), None);
+        assert_eq!(check_lock_out_above_the_field(NO_TOP_OUT_RULES), None);
+    }
+
+    #[test]
+    fn partial_lock_out_ends_the_game_only_when_checked() {
+        let partial_lock_out = TopOutRules {
+            partial_lock_out: true,
+            ..NO_TOP_OUT_RULES
+        };
+        assert_eq!(
+            check_lock_out_across_the_top(partial_lock_out),
+            Some(TopOut::PartialLockOut)
+        );
+        assert_eq!(
+            check_lock_out_above_the_field(partial_lock_out),
+            Some(TopOut::PartialLockOut)
+        );
+        assert_eq!(check_lock_out_across_the_top(NO_TOP_OUT_RULES), None);
+    }
+
+    #[test]
+    fn guideline_rules_check_block_out_and_lock_out() {
+        let rules = TopOutRules::GUIDELINE;
+        assert_eq!(check_block_out(rules), Some(TopOut::BlockOut));
+        assert_eq!(check_lock_out_above_the_field(rules), Some(TopOut::LockOut));
+        assert_eq!(check_lock_out_across_the_top(rules), None);
+    }
+
+    #[test]
+    fn block_out_only_rules_let_pieces_lock_above_the_field() {
+        let rules = TopOutRules::BLOCK_OUT_ONLY;
+        assert_eq!(check_block_out(rules), Some(TopOut::BlockOut));
+        assert_eq!(check_lock_out_above_the_field(rules), None);
+        assert_eq!(check_lock_out_across_the_top(rules), None);
+    }
+
+    fn spawned_positions(shape: Shape, ruleset: &Ruleset, config: BoardConfig) -> Vec<Position> {
+        spawn_cursor(shape, ruleset, config.num_cols, config.num_hidden_rows).get_point_positions()
+    }
+
+    #[test]
+    fn guideline_pieces_spawn_in_the_two_rows_above_the_visible_field() {
+        for num_hidden_rows in [2, 4] {
+            let config = BoardConfig {
+                num_hidden_rows,
+                ..BoardConfig::default()
+            };
+            for shape in SHAPES {
+                let positions = spawned_positions(shape, &Ruleset::modern(), config);
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let num_hidden_rows = num_hidden_rows as i32;
+                let ys: Vec<i32> = positions.iter().map(|pos| pos.y).collect();
+                assert_eq!(ys.iter().max(), Some(&(num_hidden_rows - 1)), "{shape:?}");
+                assert!(ys.iter().all(|&y| y >= num_hidden_rows - 2), "{shape:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn top_of_field_pieces_spawn_in_the_top_rows_of_the_visible_field() {
+        let config = BoardConfig::default();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let num_hidden_rows = config.num_hidden_rows as i32;
+        for shape in SHAPES {
+            let positions = spawned_positions(shape, &Ruleset::nes(), config);
+            let ys: Vec<i32> = positions.iter().map(|pos| pos.y).collect();
+            assert_eq!(ys.iter().min(), Some(&num_hidden_rows), "{shape:?}");
+            assert!(ys.iter().all(|&y| y <= num_hidden_rows + 1), "{shape:?}");
+        }
+    }
+
+    #[test]
+    fn pieces_spawn_centered_leaning_left() {
+        for num_cols in [4, 5, 10, 11, 64] {
+            let config = BoardConfig {
+                num_cols,
+                ..BoardConfig::default()
+            };
+            for shape in SHAPES {
+                let positions = spawned_positions(shape, &Ruleset::modern(), config);
+                let min_x = positions.iter().map(|pos| pos.x).min().unwrap();
+                let max_x = positions.iter().map(|pos| pos.x).max().unwrap();
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let right_gap = num_cols as i32 - 1 - max_x;
+                assert!(
+                    matches!(right_gap - min_x, 0 | 1),
+                    "{shape:?} on {num_cols} columns spans {min_x}..={max_x}"
+                );
+            }
+        }
+
+        // An even-width piece on an odd-width board can't be centered exactly.
+        let config = BoardConfig {
+            num_cols: 11,
+            ..BoardConfig::default()
+        };
+        let mut xs: Vec<i32> = spawned_positions(Shape::O, &Ruleset::modern(), config)
+            .iter()
+            .map(|pos| pos.x)
+            .collect();
+        xs.sort_unstable();
+        xs.dedup();
+        assert_eq!(xs, [4, 5]);
+    }
+
+    #[test]
+    fn the_narrowest_and_widest_boards_spawn_pieces_that_lock() {
+        for num_cols in [BoardConfig::MIN_NUM_COLS, BoardConfig::MAX_NUM_COLS] {
+            let config = BoardConfig {
+                num_cols,
+                ..BoardConfig::default()
+            };
+            let mut board = Board::new(Ruleset::modern(), config);
+            for shape in SHAPES {
+                set_active_piece(&mut board, shape);
+                assert!(board.fits_on_board(&board.cursor), "{shape:?}");
+                let outcome = board.update(TetrominoMove::UM(UserMove::HardDown));
+                assert!(outcome.is_some(), "{shape:?} on {num_cols} columns");
+            }
+        }
+    }
+
+    #[test]
+    fn guideline_pieces_drop_one_row_as_they_spawn_unless_blocked() {
+        let mut board = Board::new(Ruleset::modern(), BoardConfig::default());
+        let spawned = spawned_positions(Shape::T, &board.ruleset, BoardConfig::default());
+        set_active_piece(&mut board, Shape::T);
+        assert_eq!(board.active_piece_positions(), moved_up(&spawned, -1));
+
+        let first_visible_row = board.num_hidden_rows();
+        fill_row_with_garbage(&mut board, first_visible_row);
+        set_active_piece(&mut board, Shape::T);
+        assert_eq!(board.active_piece_positions(), spawned);
+    }
 
-fn get_points(num_rows_removed: usize) -> i32 {
-    match num_rows_removed {
-        1 => 100,
-        2 => 300,
-        3 => 500,
-        4 => 800,
-        _ => 0,
+    #[test]
+    fn top_of_field_pieces_do_not_drop_as_they_spawn() {
+        let mut board = Board::new(Ruleset::nes(), BoardConfig::default());
+        let spawned = spawned_positions(Shape::T, &board.ruleset, BoardConfig::default());
+        set_active_piece(&mut board, Shape::T);
+        assert_eq!(board.active_piece_positions(), spawned);
     }
 }
@@ -0,0 +1,248 @@
+use super::position::Position;
+
+// One bit per column of a row, bit 0 being the leftmost column.
+pub type RowMask = u64;
+
+pub const MAX_NUM_COLS: usize = RowMask::BITS as usize;
+
+// A piece in one orientation as row masks over its bounding box, so that
+// collision tests need no allocation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PieceMask {
+    // Offsets of the bounding box's top left corner from the piece's position.
+    left: i32,
+    top: i32,
+    width: i32,
+    num_rows: usize,
+    // Bit 0 is the leftmost column of the bounding box.
+    row_masks: [RowMask; 4],
+}
+
+impl PieceMask {
+    // A const fn, so that every orientation's mask can be built at compile time.
+    pub const fn from_points(local_points: &[Position; 4]) -> Self {
+        let (mut left, mut right) = (local_points[0].x, local_points[0].x);
+        let (mut top, mut bottom) = (local_points[0].y, local_points[0].y);
+        let mut i = 1;
+        while i < local_points.len() {
+            let pos = local_points[i];
+            left = if pos.x < left { pos.x } else { left };
+            right = if pos.x > right { pos.x } else { right };
+            top = if pos.y < top { pos.y } else { top };
+            bottom = if pos.y > bottom { pos.y } else { bottom };
+            i += 1;
+        }
+        let mut row_masks = [0; 4];
+        let mut i = 0;
+        #[allow(clippy::cast_sign_loss)]
+        while i < local_points.len() {
+            let pos = local_points[i];
+            row_masks[(pos.y - top) as usize] |= 1 << (pos.x - left);
+            i += 1;
+        }
+        #[allow(clippy::cast_sign_loss)]
+        PieceMask {
+            left,
+            top,
+            width: right - left + 1,
+            num_rows: (bottom - top + 1) as usize,
+            row_masks,
+        }
+    }
+}
+
+// Which cells of the board are locked, as one mask per row from the top down.
+#[derive(Clone)]
+pub struct Bitboard {
+    rows: Vec<RowMask>,
+    num_cols: usize,
+}
+
+impl Bitboard {
+    pub fn new(num_cols: usize, num_rows: usize) -> Self {
+        Bitboard {
+            rows: vec![0; num_rows],
+            num_cols,
+        }
+    }
+
+    pub fn fits(&self, mask: &PieceMask, position: Position) -> bool {
+        let left = position.x + mask.left;
+        let top = position.y + mask.top;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let (num_cols, num_rows) = (self.num_cols as i32, self.rows.len() as i32);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        if left < 0
+            || top < 0
+            || left + mask.width > num_cols
+            || top + mask.num_rows as i32 > num_rows
+        {
+            return false;
+        }
+        #[allow(clippy::cast_sign_loss)]
+        let (left, top) = (left as u32, top as usize);
+        self.rows[top..top + mask.num_rows]
+            .iter()
+            .zip(&mask.row_masks)
+            .all(|(&row, &piece_row)| row & (piece_row << left) == 0)
+    }
+
+    // The lowest y the piece can fall to from the given position.
+    pub fn drop_y(&self, mask: &PieceMask, position: Position) -> i32 {
+        let mut y = position.y;
+        while self.fits(
+            mask,
+            Position {
+                x: position.x,
+                y: y + 1,
+            },
+        ) {
+            y += 1;
+        }
+        y
+    }
+
+    pub fn set(&mut self, position: Position) {
+        #[allow(clippy::cast_sign_loss)]
+        let (x, y) = (position.x as u32, position.y as usize);
+        self.rows[y] |= 1 << x;
+    }
+
+    // Removes full rows and adds empty ones on top, keeping every other row's order.
+    // Calls `on_removed` with the index of each removed row, from the top down.
+    pub fn remove_full_rows(&mut self, mut on_removed: impl FnMut(usize)) -> usize {
+        let full_row_mask = self.full_row_mask();
+        let mut num_removed_rows = 0;
+        for y in 0..self.rows.len() {
+            if self.rows[y] == full_row_mask {
+                self.rows.copy_within(0..y, 1);
+                self.rows[0] = 0;
+                on_removed(y);
+                num_removed_rows += 1;
+            }
+        }
+        num_removed_rows
+    }
+
+    // Shifts every row up by the given row masks pushed in at the bottom. Returns
+    // true when a locked cell is pushed off the top.
+    pub fn push_rows_from_bottom(&mut self, new_rows: impl Iterator<Item = RowMask>) -> bool {
+        let mut pushed_out = false;
+        for new_row in new_rows {
+            pushed_out |= self.rows[0] != 0;
+            self.rows.copy_within(1.., 0);
+            *self.rows.last_mut().unwrap() = new_row;
+        }
+        pushed_out
+    }
+
+    pub fn full_row_mask(&self) -> RowMask {
+        RowMask::MAX >> (MAX_NUM_COLS - self.num_cols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A T pointing up, its position being the middle of its flat side.
+    const T_POINTS: [Position; 4] = [
+        Position { x: 0, y: 1 },
+        Position { x: -1, y: 1 },
+        Position { x: 1, y: 1 },
+        Position { x: 0, y: 0 },
+    ];
+
+    fn fill_row_except(bitboard: &mut Bitboard, y: i32, gap_x: i32) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        for x in (0..bitboard.num_cols as i32).filter(|&x| x != gap_x) {
+            bitboard.set(Position { x, y });
+        }
+    }
+
+    #[test]
+    fn fits_inside_an_empty_board() {
+        let bitboard = Bitboard::new(10, 20);
+        let mask = PieceMask::from_points(&T_POINTS);
+        assert!(bitboard.fits(&mask, Position { x: 1, y: 0 }));
+        assert!(bitboard.fits(&mask, Position { x: 8, y: 18 }));
+    }
+
+    #[test]
+    fn does_not_fit_past_the_walls_or_floor() {
+        let bitboard = Bitboard::new(10, 20);
+        let mask = PieceMask::from_points(&T_POINTS);
+        assert!(!bitboard.fits(&mask, Position { x: 0, y: 0 }));
+        assert!(!bitboard.fits(&mask, Position { x: 9, y: 0 }));
+        assert!(!bitboard.fits(&mask, Position { x: 4, y: -1 }));
+        assert!(!bitboard.fits(&mask, Position { x: 4, y: 19 }));
+    }
+
+    #[test]
+    fn does_not_fit_over_a_locked_cell() {
+        let mut bitboard = Bitboard::new(10, 20);
+        bitboard.set(Position { x: 5, y: 10 });
+        let mask = PieceMask::from_points(&T_POINTS);
+        assert!(!bitboard.fits(&mask, Position { x: 4, y: 9 }));
+        assert!(!bitboard.fits(&mask, Position { x: 5, y: 10 }));
+        // The corner beside the T's nose is empty.
+        assert!(bitboard.fits(&mask, Position { x: 4, y: 10 }));
+    }
+
+    #[test]
+    fn fits_the_widest_board() {
+        let mut bitboard = Bitboard::new(MAX_NUM_COLS, 4);
+        let mask = PieceMask::from_points(&T_POINTS);
+        assert!(bitboard.fits(&mask, Position { x: 62, y: 0 }));
+        bitboard.set(Position { x: 63, y: 1 });
+        assert!(!bitboard.fits(&mask, Position { x: 62, y: 0 }));
+    }
+
+    #[test]
+    fn drop_y_lands_on_the_floor() {
+        let bitboard = Bitboard::new(10, 20);
+        let mask = PieceMask::from_points(&T_POINTS);
+        assert_eq!(bitboard.drop_y(&mask, Position { x: 4, y: 0 }), 18);
+    }
+
+    #[test]
+    fn drop_y_lands_on_the_stack() {
+        let mut bitboard = Bitboard::new(10, 20);
+        fill_row_except(&mut bitboard, 15, 4);
+        let mask = PieceMask::from_points(&T_POINTS);
+        // The nose can't reach into the gap, as the flat side sits on the row.
+        assert_eq!(bitboard.drop_y(&mask, Position { x: 4, y: 0 }), 13);
+        // Already resting on the stack, the piece stays where it is.
+        assert_eq!(bitboard.drop_y(&mask, Position { x: 4, y: 13 }), 13);
+    }
+
+    #[test]
+    fn remove_full_rows_keeps_the_order_of_the_rest() {
+        let mut bitboard = Bitboard::new(10, 6);
+        bitboard.set(Position { x: 0, y: 1 });
+        fill_row_except(&mut bitboard, 2, -1);
+        bitboard.set(Position { x: 1, y: 3 });
+        fill_row_except(&mut bitboard, 4, -1);
+        fill_row_except(&mut bitboard, 5, 7);
+
+        let mut removed_rows = Vec::new();
+        let num_removed_rows = bitboard.remove_full_rows(|y| removed_rows.push(y));
+
+        assert_eq!(num_removed_rows, 2);
+        assert_eq!(removed_rows, vec![2, 4]);
+        let full_row_mask = bitboard.full_row_mask();
+        assert_eq!(
+            bitboard.rows,
+            vec![0, 0, 0, 0b1, 0b10, full_row_mask & !(1 << 7)]
+        );
+    }
+
+    #[test]
+    fn remove_full_rows_leaves_a_board_without_any_alone() {
+        let mut bitboard = Bitboard::new(10, 4);
+        fill_row_except(&mut bitboard, 3, 0);
+        let rows_before = bitboard.rows.clone();
+        assert_eq!(bitboard.remove_full_rows(|_| panic!("no row is full")), 0);
+        assert_eq!(bitboard.rows, rows_before);
+    }
+}
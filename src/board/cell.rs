@@ -1,19 +1,59 @@
+use super::cursor::piece::Shape;
+
+// Pieces are numbered in the order they lock, starting from 0.
+pub type PieceId = u64;
+// Frames of play since the game started.
+pub type Tick = u64;
+
+// The grid holds locked cells only; the active piece is kept apart from it.
 #[derive(PartialEq, Copy, Clone)]
 pub enum State {
     Empty,
-    Cursor,
     Stack,
+    Garbage,
+}
+
+// Which piece a stack cell came from and when it locked.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LockInfo {
+    pub shape: Shape,
+    // Not read by the game itself; kept for analysing placements.
+    pub piece_id: PieceId,
+    pub lock_tick: Tick,
 }
 
 #[derive(Copy, Clone)]
 pub struct Cell {
     pub state: State,
+    // Only stack cells have one.
+    pub opt_lock_info: Option<LockInfo>,
 }
 
 impl Cell {
     pub fn new() -> Self {
         Cell {
             state: State::Empty,
+            opt_lock_info: None,
+        }
+    }
+
+    pub fn stack(lock_info: LockInfo) -> Self {
+        Cell {
+            state: State::Stack,
+            opt_lock_info: Some(lock_info),
         }
     }
+
+    pub fn garbage() -> Self {
+        Cell {
+            state: State::Garbage,
+            opt_lock_info: None,
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::new()
+    }
 }
@@ -1,28 +1,28 @@
 pub mod piece;
 
 use super::position::Position;
-use macroquad::prelude::rand;
 use piece::Piece;
+use piece::RotationDir;
 use piece::Shape;
 
-#[derive(Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Cursor {
     pub position: Position,
     pub piece: Piece,
 }
 
 impl Cursor {
-    pub fn from_random_shape_in_list(shape_list: &[Shape], position: Position) -> Cursor {
+    pub fn new(shape: Shape, position: Position) -> Cursor {
         Cursor {
             position,
-            piece: Piece::new(random_shape(shape_list)),
+            piece: Piece::new(shape),
         }
     }
 
     pub fn offset_copy(&self, new_position: Position) -> Cursor {
         Cursor {
             position: new_position,
-            piece: self.piece.clone(),
+            piece: self.piece,
         }
     }
 
@@ -40,20 +40,21 @@ impl Cursor {
         }
     }
 
-    pub fn get_point_positions(&self) -> Vec<Position> {
-        let local_points = self.piece.get_local_points().clone();
-        offset_points_by_position(local_points, self.position)
+    pub fn rotate_copy(&self, dir: &RotationDir) -> Cursor {
+        match dir {
+            RotationDir::Clockwise => self.rotate_cw_copy(),
+            RotationDir::Counterclockwise => self.rotate_ccw_copy(),
+        }
     }
-}
 
-fn random_shape(shape_list: &[Shape]) -> Shape {
-    shape_list[rand::gen_range(0, shape_list.len())]
-}
-
-fn offset_points_by_position(mut points: Vec<Position>, pos: Position) -> Vec<Position> {
-    for point in &mut points {
-        point.x += pos.x;
-        point.y += pos.y;
+    pub fn get_point_positions(&self) -> Vec<Position> {
+        self.piece
+            .get_local_points()
+            .iter()
+            .map(|point| Position {
+                x: point.x + self.position.x,
+                y: point.y + self.position.y,
+            })
+            .collect()
     }
-    points
 }
@@ -1,6 +1,7 @@
+use super::super::bitboard::PieceMask;
 use super::Position;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Shape {
     O,
     I,
@@ -12,186 +13,230 @@ pub enum Shape {
 }
 
 impl Shape {
-    fn get_rotation_origin_and_initial_point_positions(self) -> (FloatPosition, Vec<Position>) {
-        type RotationOrigin = FloatPosition;
-        type P = Position;
+    // The cells in the spawn orientation and the point they turn about, both in
+    // half cells so that every turn stays in integer arithmetic.
+    #[rustfmt::skip]
+    const fn doubled_rotation_origin_and_initial_points(self) -> (Position, [Position; 4]) {
+        const fn p(x: i32, y: i32) -> Position {
+            Position { x: 2 * x, y: 2 * y }
+        }
         match self {
-            Shape::O => (
-                RotationOrigin { x: 0.5, y: 0.5 },
-                vec![
-                    P { x: 0, y: 0 },
-                    P { x: 0, y: 1 },
-                    P { x: 1, y: 0 },
-                    P { x: 1, y: 1 },
-                ],
-            ),
-            Shape::I => (
-                RotationOrigin { x: -0.5, y: 0.5 },
-                vec![
-                    P { x: -2, y: 0 },
-                    P { x: -1, y: 0 },
-                    P { x: 0, y: 0 },
-                    P { x: 1, y: 0 },
-                ],
-            ),
-            Shape::T => (
-                RotationOrigin { x: 0.0, y: 1.0 },
-                vec![
-                    P { x: 0, y: 1 },
-                    P { x: -1, y: 1 },
-                    P { x: 1, y: 1 },
-                    P { x: 0, y: 0 },
-                ],
-            ),
-            Shape::S => (
-                RotationOrigin { x: 0.0, y: 1.0 },
-                vec![
-                    P { x: -1, y: 1 },
-                    P { x: 0, y: 1 },
-                    P { x: 0, y: 0 },
-                    P { x: 1, y: 0 },
-                ],
-            ),
-            Shape::Z => (
-                RotationOrigin { x: 0.0, y: 1.0 },
-                vec![
-                    P { x: -1, y: 0 },
-                    P { x: 0, y: 0 },
-                    P { x: 0, y: 1 },
-                    P { x: 1, y: 1 },
-                ],
-            ),
-            Shape::J => (
-                RotationOrigin { x: 0.0, y: 1.0 },
-                vec![
-                    P { x: -1, y: 0 },
-                    P { x: -1, y: 1 },
-                    P { x: 0, y: 1 },
-                    P { x: 1, y: 1 },
-                ],
-            ),
-            Shape::L => (
-                RotationOrigin { x: 0.0, y: 1.0 },
-                vec![
-                    P { x: -1, y: 1 },
-                    P { x: 0, y: 1 },
-                    P { x: 1, y: 1 },
-                    P { x: 1, y: 0 },
-                ],
-            ),
+            Shape::O => (Position { x:  1, y: 1 }, [p( 0, 0), p(0, 1), p(1, 0), p(1, 1)]),
+            Shape::I => (Position { x: -1, y: 1 }, [p(-2, 0), p(-1, 0), p(0, 0), p(1, 0)]),
+            Shape::T => (Position { x:  0, y: 2 }, [p( 0, 1), p(-1, 1), p(1, 1), p(0, 0)]),
+            Shape::S => (Position { x:  0, y: 2 }, [p(-1, 1), p(0, 1), p(0, 0), p(1, 0)]),
+            Shape::Z => (Position { x:  0, y: 2 }, [p(-1, 0), p(0, 0), p(0, 1), p(1, 1)]),
+            Shape::J => (Position { x:  0, y: 2 }, [p(-1, 0), p(-1, 1), p(0, 1), p(1, 1)]),
+            Shape::L => (Position { x:  0, y: 2 }, [p(-1, 1), p(0, 1), p(1, 1), p(1, 0)]),
         }
     }
 }
 
+pub(crate) const SHAPES: [Shape; 7] = [
+    Shape::O,
+    Shape::I,
+    Shape::T,
+    Shape::S,
+    Shape::Z,
+    Shape::J,
+    Shape::L,
+];
+
+// A shape in one rotation state.
 #[derive(Copy, Clone)]
-struct FloatPosition {
-    pub x: f64,
-    pub y: f64,
+struct Orientation {
+    points: [Position; 4],
+    mask: PieceMask,
+}
+
+// Every orientation of every shape, indexed by shape and then by rotation state,
+// so that turning a piece is a table lookup.
+static ORIENTATIONS: [[Orientation; 4]; 7] = calc_orientations();
+
+const fn calc_orientations() -> [[Orientation; 4]; 7] {
+    const EMPTY: Orientation = Orientation {
+        points: [Position { x: 0, y: 0 }; 4],
+        mask: PieceMask::from_points(&[Position { x: 0, y: 0 }; 4]),
+    };
+    let mut orientations = [[EMPTY; 4]; 7];
+    let mut shape_index = 0;
+    while shape_index < SHAPES.len() {
+        let (origin, mut doubled_points) =
+            SHAPES[shape_index].doubled_rotation_origin_and_initial_points();
+        // Rotation states are listed clockwise from the spawn state.
+        let mut state_index = 0;
+        while state_index < 4 {
+            let mut points = [Position { x: 0, y: 0 }; 4];
+            let mut i = 0;
+            while i < 4 {
+                points[i] = Position {
+                    x: doubled_points[i].x / 2,
+                    y: doubled_points[i].y / 2,
+                };
+                i += 1;
+            }
+            orientations[shape_index][state_index] = Orientation {
+                points,
+                mask: PieceMask::from_points(&points),
+            };
+            doubled_points = rotate_cw(doubled_points, origin);
+            state_index += 1;
+        }
+        shape_index += 1;
+    }
+    orientations
+}
+
+// The board's origin is its upper left corner, with positive y downwards, so a
+// clockwise quarter turn about the origin takes (x, y) to (-y, x).
+const fn rotate_cw(mut doubled_points: [Position; 4], doubled_origin: Position) -> [Position; 4] {
+    let mut i = 0;
+    while i < 4 {
+        let x = doubled_points[i].x - doubled_origin.x;
+        let y = doubled_points[i].y - doubled_origin.y;
+        doubled_points[i] = Position {
+            x: doubled_origin.x - y,
+            y: doubled_origin.y + x,
+        };
+        i += 1;
+    }
+    doubled_points
+}
+
+// Orientation relative to the spawn orientation: 0, R, 2 and L in guideline terms.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RotationState {
+    Spawn,
+    Right,
+    Two,
+    Left,
 }
 
-#[derive(Clone)]
+impl RotationState {
+    #[rustfmt::skip]
+    fn rotated(self, dir: &RotationDir) -> Self {
+        match (self, dir) {
+            (RotationState::Spawn, RotationDir::Clockwise)        => RotationState::Right,
+            (RotationState::Right, RotationDir::Clockwise)        => RotationState::Two,
+            (RotationState::Two,   RotationDir::Clockwise)        => RotationState::Left,
+            (RotationState::Left,  RotationDir::Clockwise)        => RotationState::Spawn,
+            (RotationState::Spawn, RotationDir::Counterclockwise) => RotationState::Left,
+            (RotationState::Left,  RotationDir::Counterclockwise) => RotationState::Two,
+            (RotationState::Two,   RotationDir::Counterclockwise) => RotationState::Right,
+            (RotationState::Right, RotationDir::Counterclockwise) => RotationState::Spawn,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Piece {
     shape: Shape,
-    local_point_positions: Vec<Position>,
-    local_rotation_origin: FloatPosition,
+    rotation_state: RotationState,
 }
 
 impl Piece {
     pub fn new(shape: Shape) -> Self {
-        let (local_rotation_origin, local_point_positions) =
-            shape.get_rotation_origin_and_initial_point_positions();
         Piece {
             shape,
-            local_point_positions,
-            local_rotation_origin,
-        }
-    }
-
-    pub fn from(&self, local_point_positions: Vec<Position>) -> Self {
-        Piece {
-            shape: self.shape,
-            local_point_positions,
-            local_rotation_origin: self.local_rotation_origin,
+            rotation_state: RotationState::Spawn,
         }
     }
 
     pub fn rotate_cw_copy(&self) -> Self {
-        rotate_90_deg(self, &RotationDir::Clockwise)
+        self.rotate_copy(&RotationDir::Clockwise)
     }
 
     pub fn rotate_ccw_copy(&self) -> Self {
-        rotate_90_deg(self, &RotationDir::Counterclockwise)
+        self.rotate_copy(&RotationDir::Counterclockwise)
     }
 
-    pub fn get_local_points(&self) -> &Vec<Position> {
-        &self.local_point_positions
+    fn rotate_copy(&self, dir: &RotationDir) -> Self {
+        Piece {
+            shape: self.shape,
+            rotation_state: self.rotation_state.rotated(dir),
+        }
     }
-}
-
-enum RotationDir {
-    Counterclockwise,
-    Clockwise,
-}
 
-fn rotate_90_deg(piece: &Piece, dir: &RotationDir) -> Piece {
-    let mut float_points_centered_at_origin =
-        offset_to_center(&piece.local_point_positions, &piece.local_rotation_origin);
+    pub fn get_local_points(&self) -> &'static [Position] {
+        &self.orientation().points
+    }
 
-    swap_xs_and_ys(&mut float_points_centered_at_origin);
+    pub fn mask(&self) -> &'static PieceMask {
+        &self.orientation().mask
+    }
 
-    // The coordinate origin of the board is the upper left corner, such that
-    // positive x is to the right and positive y is down.
-    // Therefore after swapping x and y, to rotate clockwise, negate the xs.
-    // If the coordinate plane were instead a normal Cartesian plane where positive y is
-    // upwards, then clockwise rotation would instead require negating the ys.
-    match dir {
-        #[rustfmt::skip]
-        RotationDir::Clockwise        => negate_xs(&mut float_points_centered_at_origin),
-        RotationDir::Counterclockwise => negate_ys(&mut float_points_centered_at_origin),
+    pub fn shape(&self) -> Shape {
+        self.shape
     }
 
-    let local_point_positions = offset_from_center(
-        &float_points_centered_at_origin,
-        &piece.local_rotation_origin,
-    );
+    pub fn rotation_state(&self) -> RotationState {
+        self.rotation_state
+    }
 
-    piece.from(local_point_positions)
+    fn orientation(&self) -> &'static Orientation {
+        &ORIENTATIONS[self.shape as usize][self.rotation_state as usize]
+    }
 }
 
-fn offset_to_center(positions: &[Position], center: &FloatPosition) -> Vec<FloatPosition> {
-    positions
-        .iter()
-        .map(|&pos| FloatPosition {
-            x: f64::from(pos.x) - center.x,
-            y: f64::from(pos.y) - center.y,
-        })
-        .collect()
+pub enum RotationDir {
+    Counterclockwise,
+    Clockwise,
 }
 
-fn swap_xs_and_ys(float_posns: &mut [FloatPosition]) {
-    float_posns
-        .iter_mut()
-        .for_each(|pos| std::mem::swap(&mut pos.x, &mut pos.y));
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_points(piece: Piece) -> Vec<(i32, i32)> {
+        let mut points: Vec<_> = piece
+            .get_local_points()
+            .iter()
+            .map(|pos| (pos.x, pos.y))
+            .collect();
+        points.sort_unstable();
+        points
+    }
 
-fn negate_xs(float_posns: &mut [FloatPosition]) {
-    float_posns.iter_mut().for_each(|pos| pos.x = -pos.x);
-}
+    #[test]
+    fn i_turns_about_the_middle_of_its_box() {
+        let right = Piece::new(Shape::I).rotate_cw_copy();
+        assert_eq!(right.rotation_state(), RotationState::Right);
+        assert_eq!(sorted_points(right), vec![(0, -1), (0, 0), (0, 1), (0, 2)]);
+        let left = Piece::new(Shape::I).rotate_ccw_copy();
+        assert_eq!(
+            sorted_points(left),
+            vec![(-1, -1), (-1, 0), (-1, 1), (-1, 2)]
+        );
+    }
 
-fn negate_ys(float_posns: &mut [FloatPosition]) {
-    float_posns.iter_mut().for_each(|pos| pos.y = -pos.y);
-}
+    #[test]
+    fn t_turns_about_its_middle_cell() {
+        let right = Piece::new(Shape::T).rotate_cw_copy();
+        assert_eq!(sorted_points(right), vec![(0, 0), (0, 1), (0, 2), (1, 1)]);
+        let two = right.rotate_cw_copy();
+        assert_eq!(sorted_points(two), vec![(-1, 1), (0, 1), (0, 2), (1, 1)]);
+    }
 
-fn offset_from_center(float_positions: &[FloatPosition], center: &FloatPosition) -> Vec<Position> {
-    float_positions
-        .iter()
-        .map(|&float_pos| Position {
-            x: round_to_nearest_half(float_pos.x + center.x) as _,
-            y: round_to_nearest_half(float_pos.y + center.y) as _,
-        })
-        .collect()
-}
+    #[test]
+    fn o_does_not_move_when_turned() {
+        let spawn = Piece::new(Shape::O);
+        assert_eq!(sorted_points(spawn.rotate_cw_copy()), sorted_points(spawn));
+    }
 
-fn round_to_nearest_half(x: f64) -> f64 {
-    (x * 2.0).round() / 2.0
+    #[test]
+    fn four_quarter_turns_come_back_to_spawn() {
+        for shape in SHAPES {
+            let spawn = Piece::new(shape);
+            let turned = spawn
+                .rotate_cw_copy()
+                .rotate_cw_copy()
+                .rotate_cw_copy()
+                .rotate_cw_copy();
+            assert_eq!(turned, spawn);
+            assert_eq!(
+                spawn.rotate_ccw_copy(),
+                spawn.rotate_cw_copy().rotate_cw_copy().rotate_cw_copy()
+            );
+        }
+    }
 }
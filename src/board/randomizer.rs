@@ -0,0 +1,30 @@
+use super::cursor::piece::Shape;
+use macroquad::prelude::rand;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Randomizer {
+    // Every shape is equally likely, independent of history.
+    Uniform,
+    // Rolls an 8-sided die where the 8th side, or a repeat of the previous
+    // shape, triggers a single reroll among the 7 shapes.
+    Nes,
+}
+
+impl Randomizer {
+    pub fn next_shape(self, shape_list: &[Shape], opt_prev_shape: Option<Shape>) -> Shape {
+        match self {
+            Randomizer::Uniform => random_shape(shape_list),
+            Randomizer::Nes => {
+                let roll = rand::gen_range(0, shape_list.len() + 1);
+                match shape_list.get(roll) {
+                    Some(&shape) if Some(shape) != opt_prev_shape => shape,
+                    _ => random_shape(shape_list),
+                }
+            }
+        }
+    }
+}
+
+fn random_shape(shape_list: &[Shape]) -> Shape {
+    shape_list[rand::gen_range(0, shape_list.len())]
+}
@@ -0,0 +1,282 @@
+use super::cursor::piece::{RotationDir, RotationState, Shape};
+use super::cursor::Cursor;
+use super::position::Position;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RotationSystem {
+    // True rotation that turns in place or not at all, as the game has always played.
+    NoKicks,
+    // Super Rotation System: true rotation with guideline wall kicks.
+    Srs,
+    // Nintendo Rotation System: no kicks, T, J and L turn about their middle cell,
+    // and I, S and Z only toggle between two states, at the NES positions.
+    Nrs,
+}
+
+// How 180-degree rotations are handled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rotation180 {
+    Disabled,
+    // The piece turns in place or not at all.
+    NoKicks,
+    // The 180-degree kicks of SRS+, as in TETR.IO.
+    SrsPlus,
+}
+
+impl Rotation180 {
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "off" => Some(Rotation180::Disabled),
+            "no-kicks" => Some(Rotation180::NoKicks),
+            "srs-plus" => Some(Rotation180::SrsPlus),
+            _ => None,
+        }
+    }
+}
+
+type Offset = (i32, i32);
+
+impl RotationSystem {
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "no-kicks" => Some(RotationSystem::NoKicks),
+            "srs" => Some(RotationSystem::Srs),
+            "nrs" => Some(RotationSystem::Nrs),
+            _ => None,
+        }
+    }
+
+    // The orientation pieces spawn in. Both systems spawn pieces flat, but the NES
+    // spawns T, J and L with their flat side up.
+    pub fn spawn_rotation_state(self, shape: Shape) -> RotationState {
+        match (self, shape) {
+            (RotationSystem::Nrs, Shape::T | Shape::J | Shape::L) => RotationState::Two,
+            _ => RotationState::Spawn,
+        }
+    }
+
+    // The rotated cursors to try, in order; the first one that fits wins.
+    pub fn rotation_candidates(self, cursor: &Cursor, dir: &RotationDir) -> Candidates {
+        match self {
+            RotationSystem::NoKicks => Candidates::only(cursor.rotate_copy(dir)),
+            RotationSystem::Srs => {
+                let rotated = cursor.rotate_copy(dir);
+                let kicks = srs_kick_offsets(
+                    cursor.piece.shape(),
+                    cursor.piece.rotation_state(),
+                    rotated.piece.rotation_state(),
+                );
+                Candidates::kicked(rotated, kicks)
+            }
+            RotationSystem::Nrs => {
+                let toggles = matches!(cursor.piece.shape(), Shape::I | Shape::S | Shape::Z);
+                if !toggles {
+                    return Candidates::only(cursor.rotate_copy(dir));
+                }
+                // The NES stands I, S and Z one row higher than true rotation does.
+                match cursor.piece.rotation_state() {
+                    RotationState::Spawn => Candidates::kicked(cursor.rotate_cw_copy(), &[(0, 1)]),
+                    _ => Candidates::kicked(cursor.rotate_ccw_copy(), &[(0, -1)]),
+                }
+            }
+        }
+    }
+
+    // As `rotation_candidates`, for a half turn. Under the NRS a half turn of I, S or Z
+    // would land on the orientation they already toggle between, so it does nothing.
+    pub fn rotation_180_candidates(self, cursor: &Cursor, rotation_180: Rotation180) -> Candidates {
+        let rotated = cursor.rotate_cw_copy().rotate_cw_copy();
+        let toggles = matches!(cursor.piece.shape(), Shape::I | Shape::S | Shape::Z);
+        match (self, rotation_180) {
+            (_, Rotation180::Disabled) => Candidates::none(),
+            (RotationSystem::Nrs, _) if toggles => Candidates::none(),
+            (RotationSystem::Nrs, _) | (_, Rotation180::NoKicks) => Candidates::only(rotated),
+            (_, Rotation180::SrsPlus) => Candidates::kicked(
+                rotated,
+                srs_plus_180_kick_offsets(cursor.piece.shape(), cursor.piece.rotation_state()),
+            ),
+        }
+    }
+}
+
+// The cursors a move may end up at, in the order to try them: a cursor moved by
+// each of a list of kick offsets in turn. Nothing is allocated.
+pub struct Candidates {
+    opt_cursor: Option<Cursor>,
+    kicks: std::slice::Iter<'static, Offset>,
+}
+
+impl Candidates {
+    pub fn none() -> Self {
+        Candidates {
+            opt_cursor: None,
+            kicks: [].iter(),
+        }
+    }
+
+    pub fn only(cursor: Cursor) -> Self {
+        Candidates::kicked(cursor, &[(0, 0)])
+    }
+
+    fn kicked(cursor: Cursor, kicks: &'static [Offset]) -> Self {
+        Candidates {
+            opt_cursor: Some(cursor),
+            kicks: kicks.iter(),
+        }
+    }
+}
+
+impl Iterator for Candidates {
+    type Item = Cursor;
+
+    fn next(&mut self) -> Option<Cursor> {
+        let cursor = self.opt_cursor?;
+        let &(dx, dy) = self.kicks.next()?;
+        // The kick tables use y-up coordinates; the board is y-down.
+        Some(cursor.offset_copy(Position {
+            x: cursor.position.x + dx,
+            y: cursor.position.y - dy,
+        }))
+    }
+}
+
+#[rustfmt::skip]
+fn srs_kick_offsets(shape: Shape, from: RotationState, to: RotationState) -> &'static [Offset] {
+    use RotationState::{Left as L, Right as R, Spawn as S, Two as T};
+    match shape {
+        Shape::O => &[(0, 0)],
+        Shape::I => match (from, to) {
+            (S, R) | (L, T) => &[(0, 0), (-2, 0), ( 1, 0), (-2, -1), ( 1,  2)],
+            (R, S) | (T, L) => &[(0, 0), ( 2, 0), (-1, 0), ( 2,  1), (-1, -2)],
+            (R, T) | (S, L) => &[(0, 0), (-1, 0), ( 2, 0), (-1,  2), ( 2, -1)],
+            (T, R) | (L, S) => &[(0, 0), ( 1, 0), (-2, 0), ( 1, -2), (-2,  1)],
+            _ => &[(0, 0)],
+        },
+        Shape::T | Shape::S | Shape::Z | Shape::J | Shape::L => match (from, to) {
+            (S, R) | (T, R) => &[(0, 0), (-1, 0), (-1,  1), (0, -2), (-1, -2)],
+            (R, S) | (R, T) => &[(0, 0), ( 1, 0), ( 1, -1), (0,  2), ( 1,  2)],
+            (T, L) | (S, L) => &[(0, 0), ( 1, 0), ( 1,  1), (0, -2), ( 1, -2)],
+            (L, T) | (L, S) => &[(0, 0), (-1, 0), (-1, -1), (0,  2), (-1,  2)],
+            _ => &[(0, 0)],
+        },
+    }
+}
+
+// Kicks are listed by the orientation the piece starts from, in y-up coordinates.
+#[rustfmt::skip]
+fn srs_plus_180_kick_offsets(shape: Shape, from: RotationState) -> &'static [Offset] {
+    match (shape, from) {
+        (Shape::O, _)              => &[(0, 0)],
+        (_, RotationState::Spawn)  => &[(0, 0), ( 0,  1), ( 1,  1), (-1,  1), ( 1, 0), (-1, 0)],
+        (_, RotationState::Two)    => &[(0, 0), ( 0, -1), (-1, -1), ( 1, -1), (-1, 0), ( 1, 0)],
+        (_, RotationState::Right)  => &[(0, 0), ( 1,  0), ( 1,  2), ( 1,  1), ( 0, 2), ( 0, 1)],
+        (_, RotationState::Left)   => &[(0, 0), (-1,  0), (-1,  2), (-1,  1), ( 0, 2), ( 0, 1)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bitboard::Bitboard;
+    use super::*;
+
+    const NUM_COLS: i32 = 10;
+    const NUM_ROWS: i32 = 20;
+
+    fn empty_bitboard() -> Bitboard {
+        #[allow(clippy::cast_sign_loss)]
+        Bitboard::new(NUM_COLS as usize, NUM_ROWS as usize)
+    }
+
+    // A board filled everywhere but the cells of the given cursors.
+    fn bitboard_filled_around(cursors: &[Cursor]) -> Bitboard {
+        let mut bitboard = empty_bitboard();
+        let gaps: Vec<Position> = cursors
+            .iter()
+            .flat_map(Cursor::get_point_positions)
+            .collect();
+        for y in 0..NUM_ROWS {
+            for x in 0..NUM_COLS {
+                let pos = Position { x, y };
+                if !gaps.contains(&pos) {
+                    bitboard.set(pos);
+                }
+            }
+        }
+        bitboard
+    }
+
+    fn first_fit(mut candidates: Candidates, bitboard: &Bitboard) -> Option<Cursor> {
+        candidates.find(|cursor| bitboard.fits(cursor.piece.mask(), cursor.position))
+    }
+
+    fn cursor(shape: Shape, rotation_state: RotationState, x: i32, y: i32) -> Cursor {
+        let mut cursor = Cursor::new(shape, Position { x, y });
+        while cursor.piece.rotation_state() != rotation_state {
+            cursor = cursor.rotate_cw_copy();
+        }
+        cursor
+    }
+
+    #[test]
+    fn srs_kicks_an_upright_i_off_the_left_wall() {
+        let upright = cursor(Shape::I, RotationState::Right, 0, 5);
+        let candidates = RotationSystem::Srs.rotation_candidates(&upright, &RotationDir::Clockwise);
+        // The second kick, (-1, 0), hits the wall too; the third, (2, 0), clears it.
+        assert_eq!(
+            first_fit(candidates, &empty_bitboard()),
+            Some(cursor(Shape::I, RotationState::Two, 2, 5))
+        );
+    }
+
+    #[test]
+    fn srs_kicks_a_t_two_rows_down_into_a_t_spin_triple_slot() {
+        let flat = cursor(Shape::T, RotationState::Spawn, 4, 10);
+        let slotted = cursor(Shape::T, RotationState::Right, 3, 12);
+        let bitboard = bitboard_filled_around(&[flat, slotted]);
+        let candidates = RotationSystem::Srs.rotation_candidates(&flat, &RotationDir::Clockwise);
+        // Only the last kick, one left and two down, fits.
+        assert_eq!(first_fit(candidates, &bitboard), Some(slotted));
+    }
+
+    #[test]
+    fn srs_plus_kicks_a_half_turn_off_the_left_wall() {
+        let upright = cursor(Shape::T, RotationState::Right, 0, 5);
+        let candidates =
+            RotationSystem::Srs.rotation_180_candidates(&upright, Rotation180::SrsPlus);
+        assert_eq!(
+            first_fit(candidates, &empty_bitboard()),
+            Some(cursor(Shape::T, RotationState::Left, 1, 5))
+        );
+    }
+
+    #[test]
+    fn a_disabled_half_turn_has_no_candidates() {
+        let flat = cursor(Shape::T, RotationState::Spawn, 4, 10);
+        for rotation_system in [
+            RotationSystem::NoKicks,
+            RotationSystem::Srs,
+            RotationSystem::Nrs,
+        ] {
+            let mut candidates =
+                rotation_system.rotation_180_candidates(&flat, Rotation180::Disabled);
+            assert_eq!(candidates.next(), None, "{rotation_system:?}");
+        }
+    }
+
+    #[test]
+    fn turns_without_kicks_only_try_the_turn_in_place() {
+        let upright_i = cursor(Shape::I, RotationState::Right, 0, 5);
+        let mut candidates =
+            RotationSystem::NoKicks.rotation_candidates(&upright_i, &RotationDir::Clockwise);
+        assert_eq!(
+            candidates.next(),
+            Some(cursor(Shape::I, RotationState::Two, 0, 5))
+        );
+        assert_eq!(candidates.next(), None);
+
+        let upright_t = cursor(Shape::T, RotationState::Right, 0, 5);
+        let candidates =
+            RotationSystem::Srs.rotation_180_candidates(&upright_t, Rotation180::NoKicks);
+        assert_eq!(first_fit(candidates, &empty_bitboard()), None);
+    }
+}
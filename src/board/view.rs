@@ -0,0 +1,57 @@
+use super::cell::{self, Cell};
+use super::cursor::piece::Shape;
+use super::position::Position;
+use super::Row;
+
+// What a renderer shows in one cell: a locked cell, or the piece in play on top.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ViewCell {
+    Empty,
+    Active(Shape),
+    Stack(Shape),
+    Garbage,
+}
+
+impl From<Cell> for ViewCell {
+    fn from(cell: Cell) -> Self {
+        match (cell.state, cell.opt_lock_info) {
+            (cell::State::Stack, Some(lock_info)) => ViewCell::Stack(lock_info.shape),
+            (cell::State::Empty, _) => ViewCell::Empty,
+            // Stack cells always record their shape, so only garbage remains.
+            (cell::State::Stack | cell::State::Garbage, _) => ViewCell::Garbage,
+        }
+    }
+}
+
+pub type ViewRow = Vec<ViewCell>;
+
+pub fn rows_to_view(rows: &[Row]) -> Vec<ViewRow> {
+    rows.iter()
+        .map(|row| row.iter().map(|&cell| ViewCell::from(cell)).collect())
+        .collect()
+}
+
+// Lays the active piece over the visible rows. Its positions are in board
+// coordinates, so cells in the hidden rows are left out.
+pub fn compose(
+    visible_rows: &[Row],
+    active_shape: Shape,
+    active_piece_positions: &[Position],
+    num_hidden_rows: usize,
+) -> Vec<ViewRow> {
+    let mut view = rows_to_view(visible_rows);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let num_hidden_rows = num_hidden_rows as i32;
+    for pos in active_piece_positions {
+        let (Ok(y), Ok(x)) = (
+            usize::try_from(pos.y - num_hidden_rows),
+            usize::try_from(pos.x),
+        ) else {
+            continue;
+        };
+        if let Some(cell) = view.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *cell = ViewCell::Active(active_shape);
+        }
+    }
+    view
+}
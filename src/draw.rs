@@ -1,18 +1,35 @@
-use super::board::cell;
 use super::board::cursor;
-use super::board::cursor::piece::Piece;
+use super::board::cursor::piece::{Piece, Shape};
 use super::board::position::Position;
+use super::board::view::{self, ViewCell, ViewRow};
 use super::board::Board;
-use super::board::Row;
-use macroquad::color::colors::{BEIGE, BROWN, DARKGRAY, GRAY, GREEN, LIME, WHITE};
+use super::hud::Hud;
+use super::leaderboard::{Leaderboard, Rank};
+use super::theme::Theme;
+use backend::{RenderBackend, Viewport};
 use macroquad::color::Color;
-use macroquad::prelude::{
-    clear_background, draw_rectangle, draw_rectangle_lines, draw_text, measure_text, screen_height,
-    screen_width,
-};
-use std::thread;
+use macroquad::math::Vec2;
+use raster::RasterBackend;
+use std::time::{Duration, Instant};
+
+pub mod backend;
+mod raster;
 
 const LINE_THICKNESS: f32 = 2.0;
+// Columns to the right of the board for the piece previews and the HUD.
+const NUM_SIDE_PANEL_COLS: usize = 6;
+// Small enough for a HUD label and its value to share a line in the side panel.
+const SIDE_PANEL_FONT_SCALE: f32 = 0.7;
+// Every piece fits in four columns and two rows in its spawn orientation.
+const NUM_PREVIEW_COLS: usize = 4;
+const NUM_PREVIEW_ROWS: usize = 2;
+// The pieces after the next one are drawn smaller, so that a long queue fits.
+const QUEUED_PREVIEW_SCALE: f32 = 0.5;
+// The font size scales with the canvas height from this size at this height.
+const ORIGINAL_FONT_SIZE: f32 = 30.0;
+const ORIGINAL_CANVAS_HEIGHT: f32 = 800.0;
+// How long each frame of the row removal animation stays on screen.
+const ROW_REMOVAL_FRAME_DURATION: Duration = Duration::from_millis(60);
 
 #[derive(Clone)]
 pub struct SizeInPixels {
@@ -23,8 +40,9 @@ pub struct SizeInPixels {
 #[derive(PartialEq, Copy, Clone)]
 enum CellDisplayState {
     Empty,
-    Cursor,
-    Stack,
+    Cursor(Shape),
+    Stack(Shape),
+    Garbage,
     BeingRemoved,
 }
 
@@ -35,71 +53,91 @@ struct BoardState {
     num_cols: usize,
     latest_visible_rows: Vec<DisplayRow>,
     visible_rows_just_before_removal_of_full_rows: Vec<DisplayRow>,
-    next_piece: cursor::piece::Piece,
-    score: i32,
+    next_pieces: Vec<cursor::piece::Piece>,
+    opt_held_piece: Option<cursor::piece::Piece>,
+    hold_enabled: bool,
+    hold_used: bool,
     ghost_cursor_positions: Vec<Position>,
     num_hidden_rows: usize,
+    hud: Hud,
 }
 
-pub struct Renderer {
-    canvas_size: SizeInPixels,
-    font_size: f32,
+pub struct Renderer<B: RenderBackend> {
+    backend: B,
+    theme: Theme,
+    font_scale: f32,
+    // Snaps cells to whole physical pixels, so that they all come out the same size.
+    integer_scaling: bool,
     drawing_row_removal_animation: bool,
     animation_frames_left_to_draw: i32,
+    // When the animation frame on screen was first drawn.
+    opt_animation_frame_drawn_at: Option<Instant>,
     indices_of_full_rows_to_animate: Vec<usize>,
     animation_row: DisplayRow,
     board_state: Option<BoardState>,
-    first_frame_post_animation: bool,
 }
 
-impl Renderer {
-    pub fn new(canvas_size: &SizeInPixels) -> Self {
-        let original_canvas_height = 800.0;
-        let original_font_size = 30.0;
+impl<B: RenderBackend> Renderer<B> {
+    pub fn new(theme: Theme, font_scale: f32, integer_scaling: bool, backend: B) -> Self {
         Renderer {
-            canvas_size: canvas_size.clone(),
-            font_size: original_font_size * (canvas_size.height / original_canvas_height),
+            backend,
+            theme,
+            font_scale,
+            integer_scaling,
             drawing_row_removal_animation: false,
             animation_frames_left_to_draw: 0,
+            opt_animation_frame_drawn_at: None,
             indices_of_full_rows_to_animate: Vec::new(),
             animation_row: Vec::new(),
             board_state: None,
-            first_frame_post_animation: false,
         }
     }
 
-    pub fn draw(&mut self, board: &mut Board) {
-        let board_state = get_board_state(board);
-        let num_cols = board_state.num_cols;
-        let num_frames_to_animate = num_cols as i32 / 2;
-        let delay_between_animated_frames = std::time::Duration::from_millis(60);
+    pub fn draw(&mut self, board: &mut Board, hud: Hud) {
+        self.draw_at(board, hud, Instant::now());
+    }
+
+    // Never blocks: the animation moves on to its next frame once the one on
+    // screen has been up long enough.
+    fn draw_at(&mut self, board: &mut Board, hud: Hud, now: Instant) {
+        let board_state = get_board_state(board, hud);
+        let num_frames_to_animate = board_state.num_cols as i32 / 2;
 
         if board.row_removal_animation_is_pending() && !self.drawing_row_removal_animation {
             self.initialize_row_removal_animation(board_state.clone(), num_frames_to_animate);
         }
 
-        if self.animation_frames_left_to_draw > 0 {
-            let first_frame_of_current_animation =
-                num_frames_to_animate == self.animation_frames_left_to_draw;
-
-            self.draw_next_row_removal_animation_frame();
+        if self.drawing_row_removal_animation {
+            self.advance_row_removal_animation(now);
+        }
 
-            if !first_frame_of_current_animation {
-                thread::sleep(delay_between_animated_frames);
-            }
+        if self.animation_frames_left_to_draw > 0 {
+            self.draw_row_removal_animation_frame();
         } else {
-            self.draw_normal_non_animated_board_state(
-                board,
-                delay_between_animated_frames,
-                &board_state,
-            );
+            self.draw_normal_non_animated_board_state(board, &board_state);
         }
     }
 
+    pub fn clear_background(&mut self) {
+        self.backend.clear(self.theme.background);
+    }
+
     pub fn drawing_row_removal_animation(&self) -> bool {
         self.drawing_row_removal_animation
     }
 
+    // Worked out again for every frame, to follow the window as it is resized.
+    fn layout(&self, num_board_cols: usize, num_visible_rows: usize) -> Layout {
+        calc_layout(
+            &self.backend.screen_size(),
+            self.backend.dpi_scale(),
+            num_board_cols,
+            num_visible_rows,
+            self.font_scale,
+            self.integer_scaling,
+        )
+    }
+
     fn initialize_row_removal_animation(
         &mut self,
         board_state: BoardState,
@@ -119,120 +157,137 @@ impl Renderer {
         self.animation_row = vec![CellDisplayState::BeingRemoved; num_cols];
     }
 
-    fn draw_next_row_removal_animation_frame(&mut self) {
-        self.first_frame_post_animation = true;
-        // print_rows(
-        //     &board_state.visible_rows_just_before_removal_of_full_rows,
-        //     "Renderer before",
-        // );
-        // print_rows(&board_state.visible_rows, "Renderer after");
-        println!("{:?}", self.indices_of_full_rows_to_animate);
-        println!("anim row:");
-        print_row(&self.animation_row);
+    fn advance_row_removal_animation(&mut self, now: Instant) {
+        match self.opt_animation_frame_drawn_at {
+            None => self.opt_animation_frame_drawn_at = Some(now),
+            Some(drawn_at) if now.duration_since(drawn_at) >= ROW_REMOVAL_FRAME_DURATION => {
+                make_next_frame_of_row_removal_animation(
+                    &mut self
+                        .board_state
+                        .as_mut()
+                        .unwrap()
+                        .visible_rows_just_before_removal_of_full_rows,
+                    &self.indices_of_full_rows_to_animate,
+                    &mut self.animation_row,
+                );
+                self.animation_frames_left_to_draw -= 1;
+                self.opt_animation_frame_drawn_at = Some(now);
+            }
+            Some(_) => (),
+        }
+    }
+
+    fn draw_row_removal_animation_frame(&mut self) {
+        let board_state = self.board_state.as_ref().unwrap();
+        let layout = self.layout(board_state.num_cols, board_state.latest_visible_rows.len());
         draw_helper(
-            self.board_state.as_ref().unwrap(),
+            &mut self.backend,
+            board_state,
             &DrawMode::AnimatingRowRemoval,
-            &self.canvas_size,
-            self.font_size,
+            &layout,
+            &self.theme,
         );
-
-        make_next_frame_of_row_removal_animation(
-            &mut self
-                .board_state
-                .as_mut()
-                .unwrap()
-                .visible_rows_just_before_removal_of_full_rows,
-            &self.indices_of_full_rows_to_animate,
-            &mut self.animation_row,
-        );
-        self.animation_frames_left_to_draw -= 1;
     }
 
     fn draw_normal_non_animated_board_state(
         &mut self,
         board: &mut Board,
-        delay_between_animated_frames: std::time::Duration,
         board_state: &BoardState,
     ) {
         board.set_row_removal_animation_is_pending_to_false();
 
         self.drawing_row_removal_animation = false;
+        self.opt_animation_frame_drawn_at = None;
 
-        if self.first_frame_post_animation {
-            self.first_frame_post_animation = false;
-            thread::sleep(delay_between_animated_frames);
-        }
-
+        let layout = self.layout(board_state.num_cols, board_state.latest_visible_rows.len());
         draw_helper(
+            &mut self.backend,
             board_state,
             &DrawMode::NotAnimatingRowRemoval,
-            &self.canvas_size,
-            self.font_size,
+            &layout,
+            &self.theme,
         );
     }
 }
 
-fn print_rows(rows: &[DisplayRow], desc: &str) {
-    println!("{desc}:");
-    for row in rows {
-        print_row(row);
-    }
-}
+// The size of a cell in snapshots rendered without a window.
+const SNAPSHOT_CELL_SIZE: usize = 32;
 
-fn print_row(row: &DisplayRow) {
-    print!("|");
-    for &cell in row {
-        print_cell(cell);
-    }
-    println!("|");
-}
-
-fn print_cell(cell_display_state: CellDisplayState) {
-    let ch = match cell_display_state {
-        CellDisplayState::Empty => " ",
-        CellDisplayState::Cursor => "*",
-        CellDisplayState::Stack => "x",
-        CellDisplayState::BeingRemoved => "y",
-    };
-    print!("{ch}");
+// Draws the board as it would appear in the window, without a window or GPU, and
+// saves it as a PNG file.
+pub fn render_to_png(board: &Board, theme: &Theme, path: &str) -> Result<(), String> {
+    let board_state = get_board_state(board, Hud::new(board, Duration::ZERO));
+    let width = (board_state.num_cols + NUM_SIDE_PANEL_COLS) * SNAPSHOT_CELL_SIZE;
+    let height = board_state.latest_visible_rows.len() * SNAPSHOT_CELL_SIZE;
+    let mut backend = RasterBackend::new(width, height);
+    let layout = calc_layout(
+        &backend.screen_size(),
+        backend.dpi_scale(),
+        board_state.num_cols,
+        board_state.latest_visible_rows.len(),
+        1.0,
+        false,
+    );
+    backend.clear(theme.background);
+    draw_helper(
+        &mut backend,
+        &board_state,
+        &DrawMode::NotAnimatingRowRemoval,
+        &layout,
+        theme,
+    );
+    backend.save_png(path)
 }
 
-fn get_board_state(board: &Board) -> BoardState {
+fn get_board_state(board: &Board, hud: Hud) -> BoardState {
     let num_cols = board.num_cols();
-    let visible_rows = rows_to_display_rows(board.visible_rows().to_vec());
-    let visible_rows_just_before_removal_of_full_rows = rows_to_display_rows(
-        board
-            .visible_rows_just_before_removal_of_full_rows()
-            .to_vec(),
-    );
-    let next_piece = board.next_piece().clone();
-    let score = board.score();
-    let ghost_cursor_positions = board.ghost_cursor_positions();
+    let visible_rows = view_to_display_rows(board.visible_view());
+    let visible_rows_just_before_removal_of_full_rows = view_to_display_rows(view::rows_to_view(
+        board.visible_rows_just_before_removal_of_full_rows(),
+    ));
+    let next_pieces = board
+        .next_pieces(board.ruleset().num_next_pieces)
+        .cloned()
+        .collect();
+    let opt_held_piece = board.held_piece();
+    let hold_enabled = board.ruleset().hold_enabled;
+    let hold_used = board.hold_used();
+    let ghost_cursor_positions = if board.ruleset().ghost_enabled {
+        board.ghost_cursor_positions()
+    } else {
+        Vec::new()
+    };
     let num_hidden_rows = board.num_hidden_rows();
     BoardState {
         num_cols,
         latest_visible_rows: visible_rows,
         visible_rows_just_before_removal_of_full_rows,
-        next_piece,
-        score,
+        next_pieces,
+        opt_held_piece,
+        hold_enabled,
+        hold_used,
         ghost_cursor_positions,
         num_hidden_rows,
+        hud,
     }
 }
 
-fn rows_to_display_rows(src: Vec<Row>) -> Vec<DisplayRow> {
-    src.into_iter().map(row_to_display_row).collect()
+fn view_to_display_rows(src: Vec<ViewRow>) -> Vec<DisplayRow> {
+    src.into_iter().map(view_row_to_display_row).collect()
 }
 
-fn row_to_display_row(src: Row) -> DisplayRow {
-    src.into_iter().map(cell_to_cell_display_state).collect()
+fn view_row_to_display_row(src: ViewRow) -> DisplayRow {
+    src.into_iter()
+        .map(view_cell_to_cell_display_state)
+        .collect()
 }
 
-fn cell_to_cell_display_state(src: cell::Cell) -> CellDisplayState {
-    match src.state {
-        cell::State::Empty => CellDisplayState::Empty,
-        cell::State::Cursor => CellDisplayState::Cursor,
-        cell::State::Stack => CellDisplayState::Stack,
+fn view_cell_to_cell_display_state(src: ViewCell) -> CellDisplayState {
+    match src {
+        ViewCell::Empty => CellDisplayState::Empty,
+        ViewCell::Active(shape) => CellDisplayState::Cursor(shape),
+        ViewCell::Stack(shape) => CellDisplayState::Stack(shape),
+        ViewCell::Garbage => CellDisplayState::Garbage,
     }
 }
 
@@ -245,8 +300,12 @@ fn get_indices_of_full_rows(rows: &[DisplayRow]) -> Vec<usize> {
 }
 
 fn is_full(row: &DisplayRow) -> bool {
-    row.iter()
-        .all(|&cell_display_state| cell_display_state == CellDisplayState::Stack)
+    row.iter().all(|&cell_display_state| {
+        matches!(
+            cell_display_state,
+            CellDisplayState::Stack(_) | CellDisplayState::Garbage
+        )
+    })
 }
 
 enum DrawMode {
@@ -255,34 +314,35 @@ enum DrawMode {
 }
 
 fn draw_helper(
+    backend: &mut impl RenderBackend,
     board_state: &BoardState,
     draw_mode: &DrawMode,
-    canvas_size: &SizeInPixels,
-    font_size: f32,
+    layout: &Layout,
+    theme: &Theme,
 ) {
-    let num_board_cols = board_state.num_cols;
+    let backend = &mut Viewport::new(backend, layout.origin, layout.canvas_size.clone());
     let visible_rows = match draw_mode {
         DrawMode::NotAnimatingRowRemoval => &board_state.latest_visible_rows,
         DrawMode::AnimatingRowRemoval => &board_state.visible_rows_just_before_removal_of_full_rows,
     };
 
-    let cell_size = calc_cell_size_in_pixels(canvas_size, num_board_cols, visible_rows.len());
-
-    draw_preview_of_next_piece(&board_state.next_piece, num_board_cols, cell_size);
+    let cell_size = layout.cell_size;
 
-    draw_score(board_state.score, num_board_cols, cell_size, font_size);
+    draw_side_panel(backend, board_state, cell_size, layout.font_size, theme);
 
     for (y, row) in visible_rows.iter().enumerate() {
         for (x, cell_display_state) in row.iter().enumerate() {
-            draw_cell(*cell_display_state, x, y, cell_size);
+            draw_cell(backend, *cell_display_state, x, y, cell_size, theme);
         }
     }
 
     if matches!(draw_mode, DrawMode::NotAnimatingRowRemoval) {
         draw_ghost_cursor(
+            backend,
             &board_state.ghost_cursor_positions,
             board_state.num_hidden_rows,
             cell_size,
+            theme,
         );
     }
 }
@@ -294,11 +354,9 @@ fn make_next_frame_of_row_removal_animation(
 ) {
     enlarge_middle_gap(animation_row);
 
-    print_rows(rows, "Before replacement");
     for &index in indices_of_full_rows_to_animate {
         (*rows)[index].clone_from(animation_row);
     }
-    print_rows(rows, "After replacement");
 }
 
 fn enlarge_middle_gap(animation_row: &mut DisplayRow) {
@@ -323,95 +381,381 @@ fn enlarge_middle_gap(animation_row: &mut DisplayRow) {
     animation_row[i2] = CellDisplayState::Empty;
 }
 
-fn calc_cell_size_in_pixels(
-    canvas_size: &SizeInPixels,
+// Where the board and side panel go on the screen.
+struct Layout {
+    origin: Vec2,
+    canvas_size: SizeInPixels,
+    cell_size: f32,
+    font_size: f32,
+}
+
+// The board and side panel keep their aspect ratio at any screen size: they are
+// scaled to fit and centered, with bars of background along the other two sides.
+fn calc_layout(
+    screen_size: &SizeInPixels,
+    dpi_scale: f32,
     num_board_cols: usize,
     num_visible_board_rows: usize,
-) -> f32 {
-    let cell_size_from_width = canvas_size.width / num_board_cols as f32;
-    let cell_size_from_height = canvas_size.height / num_visible_board_rows as f32;
-    cell_size_from_width.min(cell_size_from_height)
-}
+    font_scale: f32,
+    integer_scaling: bool,
+) -> Layout {
+    let num_cols_incl_side_panel = num_board_cols + NUM_SIDE_PANEL_COLS;
+    let cell_size_from_width = screen_size.width / num_cols_incl_side_panel as f32;
+    let cell_size_from_height = screen_size.height / num_visible_board_rows as f32;
+    let mut cell_size = cell_size_from_width.min(cell_size_from_height);
+    if integer_scaling {
+        cell_size = (cell_size * dpi_scale).floor().max(1.0) / dpi_scale;
+    }
 
-fn draw_score(score: i32, num_board_cols: usize, cell_size: f32, font_size: f32) {
-    let spacer_cols_x = 2;
-    let pixel_offset_x = (num_board_cols + spacer_cols_x) as f32 * cell_size;
-    let pixel_offset_y = 40.0;
-    draw_text(
-        score.to_string().as_str(),
-        pixel_offset_x,
-        pixel_offset_y,
-        font_size,
-        DARKGRAY,
+    let canvas_size = SizeInPixels {
+        width: num_cols_incl_side_panel as f32 * cell_size,
+        height: num_visible_board_rows as f32 * cell_size,
+    };
+    let mut origin = Vec2::new(
+        (screen_size.width - canvas_size.width) / 2.,
+        (screen_size.height - canvas_size.height) / 2.,
     );
+    if integer_scaling {
+        origin = (origin * dpi_scale).floor() / dpi_scale;
+    }
+    let font_size = ORIGINAL_FONT_SIZE * (canvas_size.height / ORIGINAL_CANVAS_HEIGHT) * font_scale;
+    Layout {
+        origin,
+        canvas_size,
+        cell_size,
+        font_size,
+    }
 }
 
-fn draw_preview_of_next_piece(next_piece: &Piece, num_board_cols: usize, cell_size: f32) {
-    let base_col_idx: usize = num_board_cols + 3;
-    let base_row_idx: usize = 2;
-    for &pos in next_piece.get_local_points() {
-        let cell_col_idx = (base_col_idx as i32 + pos.x) as usize;
-        let cell_row_idx = (base_row_idx as i32 + pos.y) as usize;
-        draw_cell(
-            CellDisplayState::Cursor,
-            cell_col_idx,
-            cell_row_idx,
-            cell_size,
-        );
+// Everything in the side panel scales with the cell and font sizes, so when a
+// long queue or HUD would run past the bottom of the canvas, it is all shrunk
+// by the same amount to fit.
+fn draw_side_panel(
+    backend: &mut impl RenderBackend,
+    board_state: &BoardState,
+    cell_size: f32,
+    font_size: f32,
+    theme: &Theme,
+) {
+    let font_size = font_size * SIDE_PANEL_FONT_SCALE;
+    let mut measured_panel = SidePanel::new(board_state.num_cols, cell_size, font_size, 1.0);
+    measured_panel.drawing = false;
+    fill_side_panel(&mut measured_panel, backend, board_state, theme);
+    let scale = (backend.screen_size().height / measured_panel.height()).min(1.0);
+
+    let mut panel = SidePanel::new(board_state.num_cols, cell_size, font_size, scale);
+    fill_side_panel(&mut panel, backend, board_state, theme);
+}
+
+fn fill_side_panel(
+    panel: &mut SidePanel,
+    backend: &mut impl RenderBackend,
+    board_state: &BoardState,
+    theme: &Theme,
+) {
+    if let Some((next_piece, queued_pieces)) = board_state.next_pieces.split_first() {
+        panel.text(backend, "Next", theme.text);
+        let cell_display_state = CellDisplayState::Cursor(next_piece.shape());
+        panel.piece(backend, Some(next_piece), cell_display_state, 1.0, theme);
+        for queued_piece in queued_pieces {
+            panel.small_gap();
+            let cell_display_state = CellDisplayState::Cursor(queued_piece.shape());
+            panel.piece(
+                backend,
+                Some(queued_piece),
+                cell_display_state,
+                QUEUED_PREVIEW_SCALE,
+                theme,
+            );
+        }
+        panel.gap();
+    }
+
+    if board_state.hold_enabled {
+        panel.text(backend, "Hold", theme.text);
+        let opt_held_piece = board_state.opt_held_piece.as_ref();
+        // Greyed out while the piece in play has already been swapped.
+        let cell_display_state = match opt_held_piece {
+            Some(held_piece) if !board_state.hold_used => {
+                CellDisplayState::Cursor(held_piece.shape())
+            }
+            _ => CellDisplayState::Garbage,
+        };
+        panel.piece(backend, opt_held_piece, cell_display_state, 1.0, theme);
+        panel.gap();
+    }
+
+    let hud = &board_state.hud;
+    for (label, value) in hud.entries() {
+        panel.entry(backend, label, &value, theme.text);
+    }
+    if !hud.status_lines.is_empty() {
+        panel.gap();
+        for line in &hud.status_lines {
+            panel.text(backend, line, theme.text);
+        }
+    }
+    if let Some(streak_text) = hud.streak_text() {
+        panel.gap();
+        panel.text(backend, &streak_text, theme.text);
     }
 }
 
-fn draw_cell(cell_display_state: CellDisplayState, col_idx: usize, row_idx: usize, cell_size: f32) {
-    #[rustfmt::skip]
-    let outline_color = match cell_display_state {
-        CellDisplayState::Empty        => Color::new(0.99, 0.99, 0.99, 1.00),
-        CellDisplayState::Cursor       => BEIGE,
-        CellDisplayState::Stack        => GRAY,
-        CellDisplayState::BeingRemoved => LIME,
-    };
+// Stacks the contents of the side panel from the top down, each part below the
+// one before, so that parts never overlap one another.
+struct SidePanel {
+    left: f32,
+    right: f32,
+    y: f32,
+    cell_size: f32,
+    font_size: f32,
+    // When off, the panel only works out how tall its contents are.
+    drawing: bool,
+}
 
-    #[rustfmt::skip]
-    let fill_color = match cell_display_state {
-        CellDisplayState::Empty         => WHITE,
-        CellDisplayState::Cursor        => BROWN,
-        CellDisplayState::Stack         => DARKGRAY,
-        CellDisplayState::BeingRemoved  => GREEN,
-    };
+impl SidePanel {
+    // A column of space separates the panel from the board, and half a cell of
+    // margin surrounds the rest. The contents are scaled by `scale`.
+    fn new(num_board_cols: usize, cell_size: f32, font_size: f32, scale: f32) -> Self {
+        SidePanel {
+            left: (num_board_cols + 1) as f32 * cell_size,
+            right: (num_board_cols + NUM_SIDE_PANEL_COLS) as f32 * cell_size - cell_size / 2.,
+            y: cell_size * scale / 2.,
+            cell_size: cell_size * scale,
+            font_size: font_size * scale,
+            drawing: true,
+        }
+    }
 
-    draw_rectangle_lines(
+    // The height of what has been stacked so far, with the bottom margin.
+    fn height(&self) -> f32 {
+        self.y + self.cell_size / 2.
+    }
+
+    fn baseline(&self) -> f32 {
+        self.y + self.font_size * 0.8
+    }
+
+    fn next_line(&mut self) {
+        self.y += self.font_size * 1.25;
+    }
+
+    fn gap(&mut self) {
+        self.y += self.cell_size / 2.;
+    }
+
+    fn small_gap(&mut self) {
+        self.y += self.cell_size / 4.;
+    }
+
+    fn text(&mut self, backend: &mut impl RenderBackend, text: &str, color: Color) {
+        if self.drawing {
+            backend.text(text, self.left, self.baseline(), self.font_size, color);
+        }
+        self.next_line();
+    }
+
+    // The label on the left and the value aligned to the right.
+    fn entry(&mut self, backend: &mut impl RenderBackend, label: &str, value: &str, color: Color) {
+        if self.drawing {
+            let baseline = self.baseline();
+            backend.text(label, self.left, baseline, self.font_size, color);
+            let value_width = backend.measure(value, self.font_size).width;
+            backend.text(
+                value,
+                self.right - value_width,
+                baseline,
+                self.font_size,
+                color,
+            );
+        }
+        self.next_line();
+    }
+
+    // Centers the piece in a box of preview cells, scaled down from board cells by
+    // `scale`. The box is left empty when there is no piece to show.
+    fn piece(
+        &mut self,
+        backend: &mut impl RenderBackend,
+        opt_piece: Option<&Piece>,
+        cell_display_state: CellDisplayState,
+        scale: f32,
+        theme: &Theme,
+    ) {
+        let cell_size = self.cell_size * scale;
+        if let (true, Some(piece)) = (self.drawing, opt_piece) {
+            let points = piece.get_local_points();
+            let min_x = points.iter().map(|pos| pos.x).min().unwrap_or(0);
+            let max_x = points.iter().map(|pos| pos.x).max().unwrap_or(0);
+            let min_y = points.iter().map(|pos| pos.y).min().unwrap_or(0);
+            let max_y = points.iter().map(|pos| pos.y).max().unwrap_or(0);
+            let left =
+                self.left + (NUM_PREVIEW_COLS as f32 - (max_x - min_x + 1) as f32) * cell_size / 2.;
+            let top =
+                self.y + (NUM_PREVIEW_ROWS as f32 - (max_y - min_y + 1) as f32) * cell_size / 2.;
+            for pos in points {
+                draw_cell_at(
+                    backend,
+                    cell_display_state,
+                    left + (pos.x - min_x) as f32 * cell_size,
+                    top + (pos.y - min_y) as f32 * cell_size,
+                    cell_size,
+                    theme,
+                );
+            }
+        }
+        self.y += NUM_PREVIEW_ROWS as f32 * cell_size;
+    }
+}
+
+fn draw_cell(
+    backend: &mut impl RenderBackend,
+    cell_display_state: CellDisplayState,
+    col_idx: usize,
+    row_idx: usize,
+    cell_size: f32,
+    theme: &Theme,
+) {
+    draw_cell_at(
+        backend,
+        cell_display_state,
         col_idx as f32 * cell_size,
         row_idx as f32 * cell_size,
         cell_size,
-        cell_size,
-        LINE_THICKNESS,
-        outline_color,
+        theme,
     );
+}
+
+// Draws a cell with its top left corner at the given pixel position.
+fn draw_cell_at(
+    backend: &mut impl RenderBackend,
+    cell_display_state: CellDisplayState,
+    x: f32,
+    y: f32,
+    cell_size: f32,
+    theme: &Theme,
+) {
+    #[rustfmt::skip]
+    let colors = match cell_display_state {
+        CellDisplayState::Empty         => theme.empty,
+        CellDisplayState::Cursor(shape) => theme.active(shape),
+        CellDisplayState::Stack(shape)  => theme.shape(shape),
+        CellDisplayState::Garbage       => theme.garbage,
+        CellDisplayState::BeingRemoved  => theme.being_removed,
+    };
+
+    backend.outline(x, y, cell_size, cell_size, LINE_THICKNESS, colors.outline);
 
-    draw_rectangle(
-        col_idx as f32 * cell_size + LINE_THICKNESS / 2.,
-        row_idx as f32 * cell_size + LINE_THICKNESS / 2.,
+    backend.rect(
+        x + LINE_THICKNESS / 2.,
+        y + LINE_THICKNESS / 2.,
         cell_size - LINE_THICKNESS,
         cell_size - LINE_THICKNESS,
-        fill_color,
+        colors.fill,
     );
+
+    if let Some(texture) = &theme.opt_texture {
+        if cell_display_state != CellDisplayState::Empty {
+            backend.texture(
+                texture,
+                x + LINE_THICKNESS / 2.,
+                y + LINE_THICKNESS / 2.,
+                cell_size - LINE_THICKNESS,
+                cell_size - LINE_THICKNESS,
+                colors.fill,
+            );
+        }
+    }
+
+    if theme.patterns {
+        if let CellDisplayState::Cursor(shape) | CellDisplayState::Stack(shape) = cell_display_state
+        {
+            draw_shape_pattern(backend, shape, x, y, cell_size, colors.outline);
+        }
+    }
+}
+
+// A small mark in the middle of the cell that differs for every shape.
+fn draw_shape_pattern(
+    backend: &mut impl RenderBackend,
+    shape: Shape,
+    left: f32,
+    top: f32,
+    cell_size: f32,
+    color: Color,
+) {
+    let (x, y) = (left + cell_size / 2., top + cell_size / 2.);
+    let r = cell_size * 0.2;
+    let mut line = |x1, y1, x2, y2| {
+        backend.line(Vec2::new(x1, y1), Vec2::new(x2, y2), LINE_THICKNESS, color);
+    };
+    match shape {
+        Shape::I => line(x - r, y, x + r, y),
+        Shape::S => line(x - r, y + r, x + r, y - r),
+        Shape::Z => line(x - r, y - r, x + r, y + r),
+        Shape::L => {
+            line(x - r, y, x + r, y);
+            line(x, y - r, x, y + r);
+        }
+        Shape::O => backend.outline(x - r, y - r, 2. * r, 2. * r, LINE_THICKNESS, color),
+        Shape::T => backend.triangle_outline(
+            [
+                Vec2::new(x, y - r),
+                Vec2::new(x - r, y + r),
+                Vec2::new(x + r, y + r),
+            ],
+            LINE_THICKNESS,
+            color,
+        ),
+        Shape::J => backend.circle(Vec2::new(x, y), r * 0.6, color),
+    }
 }
 
 fn draw_ghost_cursor(
+    backend: &mut impl RenderBackend,
     ghost_cursor_cell_positions: &[Position],
     num_hidden_board_rows: usize,
     cell_size: f32,
+    theme: &Theme,
 ) {
-    ghost_cursor_cell_positions
-        .iter()
-        .for_each(|&pos| draw_ghost_cursor_cell(pos, num_hidden_board_rows, cell_size));
+    ghost_cursor_cell_positions.iter().for_each(|&pos| {
+        draw_ghost_cursor_cell(
+            backend,
+            pos,
+            num_hidden_board_rows,
+            cell_size,
+            theme.ghost,
+            theme.ghost_opacity,
+        );
+    });
 }
 
-fn draw_ghost_cursor_cell(position: Position, num_hidden_board_rows: usize, cell_size: f32) {
+fn draw_ghost_cursor_cell(
+    backend: &mut impl RenderBackend,
+    position: Position,
+    num_hidden_board_rows: usize,
+    cell_size: f32,
+    outline_color: Color,
+    fill_opacity: f32,
+) {
     let col_idx = position.x;
     let row_idx = position.y - num_hidden_board_rows as i32;
 
-    let outline_color = BEIGE;
-    draw_rectangle_lines(
+    if fill_opacity > 0.0 {
+        let fill_color = Color {
+            a: fill_opacity,
+            ..outline_color
+        };
+        backend.rect(
+            col_idx as f32 * cell_size + LINE_THICKNESS / 2.,
+            row_idx as f32 * cell_size + LINE_THICKNESS / 2.,
+            cell_size - LINE_THICKNESS,
+            cell_size - LINE_THICKNESS,
+            fill_color,
+        );
+    }
+
+    backend.outline(
         col_idx as f32 * cell_size + LINE_THICKNESS / 4.0,
         row_idx as f32 * cell_size + LINE_THICKNESS / 4.0,
         cell_size - LINE_THICKNESS / 2.0,
@@ -421,39 +765,452 @@ fn draw_ghost_cursor_cell(position: Position, num_hidden_board_rows: usize, cell
     );
 }
 
-impl Renderer {
-    pub fn draw_game_over_screen(&self, board: &Board) {
-        clear_background(WHITE);
+impl<B: RenderBackend> Renderer<B> {
+    pub fn draw_game_over_screen(
+        &mut self,
+        board: &Board,
+        headline: &str,
+        summary_lines: &[String],
+        opt_leaderboard_result: Option<(&Leaderboard, Option<Rank>)>,
+    ) {
+        let lines = end_screen_lines(board, headline, summary_lines, opt_leaderboard_result);
+        self.draw_centered_lines(board, &lines, self.theme.text);
+    }
+
+    pub fn draw_victory_screen(
+        &mut self,
+        board: &Board,
+        headline: &str,
+        summary_lines: &[String],
+        opt_leaderboard_result: Option<(&Leaderboard, Option<Rank>)>,
+    ) {
+        let lines = end_screen_lines(board, headline, summary_lines, opt_leaderboard_result);
+        self.draw_centered_lines(board, &lines, self.theme.accent);
+    }
+
+    // In the font size of the game screen the lines replace.
+    fn draw_centered_lines(&mut self, board: &Board, lines: &[String], color: Color) {
+        let font_size = self
+            .layout(board.num_cols(), board.num_visible_rows())
+            .font_size;
+        let backend = &mut self.backend;
+        backend.clear(self.theme.background);
 
-        let font_size = self.font_size;
+        let screen_size = backend.screen_size();
 
-        let y_base = screen_height() / 2.0;
-        let final_score = board.score();
-        let high_score = board.high_score();
-        let lines = [
-            String::from("Game Over"),
-            format!("Final score: {final_score}"),
-            format!("High score: {high_score}"),
-            String::from("Press [enter] to play again, q to exit"),
-        ];
+        let y_base = screen_size.height / 2.0 - lines.len() as f32 * font_size / 2.0;
 
         let opt_tallest_line = lines.iter().max_by_key(|line| {
-            let dimensions = measure_text(line, None, font_size as _, 1.0);
+            let dimensions = backend.measure(line, font_size);
             dimensions.height as i32
         });
-        let size_of_tallest_line =
-            measure_text(opt_tallest_line.unwrap(), None, font_size as _, 1.0);
+        let size_of_tallest_line = backend.measure(opt_tallest_line.unwrap(), font_size);
         let line_spacing = size_of_tallest_line.height * 1.5;
 
         for (i, text) in lines.iter().enumerate() {
-            let text_size = measure_text(text, None, font_size as _, 1.0);
-            draw_text(
+            let text_size = backend.measure(text, font_size);
+            backend.text(
                 text,
-                screen_width() / 2. - text_size.width / 2.,
+                screen_size.width / 2. - text_size.width / 2.,
                 y_base + (i as f32 * line_spacing) + line_spacing / 2.,
                 font_size,
-                DARKGRAY,
+                color,
             );
         }
     }
 }
+
+// The text of the game over and victory screens, shared by the frontends.
+pub fn end_screen_lines(
+    board: &Board,
+    headline: &str,
+    summary_lines: &[String],
+    opt_leaderboard_result: Option<(&Leaderboard, Option<Rank>)>,
+) -> Vec<String> {
+    let final_score = board.score();
+    let high_score = board.high_score();
+    let mut lines = vec![String::from(headline)];
+    lines.extend_from_slice(summary_lines);
+    lines.push(format!("Final score: {final_score}"));
+    if let Some((leaderboard, opt_rank)) = opt_leaderboard_result {
+        lines.push(String::from("Leaderboard:"));
+        for (rank, score) in leaderboard.scores().iter().enumerate() {
+            let marker = if opt_rank == Some(rank) { " <" } else { "" };
+            lines.push(format!("{}. {score}{marker}", rank + 1));
+        }
+    } else {
+        lines.push(format!("High score: {high_score}"));
+    }
+    lines.push(String::from("Press [enter] to play again, q to exit"));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backend::{DrawCommand, RecordingBackend};
+    use super::*;
+    use crate::board::BoardConfig;
+    use crate::ruleset::Ruleset;
+    use CellDisplayState::{BeingRemoved, Empty, Garbage, Stack};
+
+    // With the side panel, four columns and four rows come to 100 by 40 pixels,
+    // which makes cells 10 pixels wide.
+    const CELL_SIZE: f32 = 10.0;
+    const SCREEN_SIZE: SizeInPixels = SizeInPixels {
+        width: 100.0,
+        height: 40.0,
+    };
+
+    fn small_board() -> Board {
+        Board::new(
+            Ruleset::modern(),
+            BoardConfig {
+                num_cols: 4,
+                num_visible_rows: 4,
+                num_hidden_rows: 2,
+            },
+        )
+    }
+
+    // The board's own state with the random parts replaced by the given rows and
+    // nothing in the side panel but the HUD.
+    fn board_state(board: &Board, rows: Vec<DisplayRow>) -> BoardState {
+        BoardState {
+            latest_visible_rows: rows.clone(),
+            visible_rows_just_before_removal_of_full_rows: rows,
+            next_pieces: Vec::new(),
+            opt_held_piece: None,
+            hold_enabled: false,
+            ghost_cursor_positions: Vec::new(),
+            ..get_board_state(board, Hud::new(board, Duration::ZERO))
+        }
+    }
+
+    // Draws on a screen with cells 10 pixels wide, as tall as the board's rows.
+    fn record(board_state: &BoardState, theme: &Theme) -> Vec<DrawCommand> {
+        let num_rows = board_state.latest_visible_rows.len();
+        let mut backend = RecordingBackend::new(SizeInPixels {
+            width: SCREEN_SIZE.width,
+            height: num_rows as f32 * CELL_SIZE,
+        });
+        let layout = calc_layout(&backend.screen_size(), 1.0, 4, num_rows, 1.0, false);
+        draw_helper(
+            &mut backend,
+            board_state,
+            &DrawMode::NotAnimatingRowRemoval,
+            &layout,
+            theme,
+        );
+        backend.take_commands()
+    }
+
+    // The fills of cells drawn at the given size, as their top left corners and colors.
+    fn cell_fills(commands: &[DrawCommand], cell_size: f32) -> Vec<(f32, f32, Color)> {
+        commands
+            .iter()
+            .filter_map(|command| match *command {
+                DrawCommand::Rect {
+                    x, y, width, color, ..
+                } if width == cell_size - LINE_THICKNESS => {
+                    Some((x - LINE_THICKNESS / 2., y - LINE_THICKNESS / 2., color))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn board_fills(rows: &[DisplayRow], theme: &Theme) -> Vec<(f32, f32, Color)> {
+        let mut fills = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &cell_display_state) in row.iter().enumerate() {
+                let colors = match cell_display_state {
+                    Empty => theme.empty,
+                    CellDisplayState::Cursor(shape) => theme.active(shape),
+                    Stack(shape) => theme.shape(shape),
+                    Garbage => theme.garbage,
+                    BeingRemoved => theme.being_removed,
+                };
+                fills.push((x as f32 * CELL_SIZE, y as f32 * CELL_SIZE, colors.fill));
+            }
+        }
+        fills
+    }
+
+    fn texts(commands: &[DrawCommand]) -> Vec<(&str, f32, f32, Color)> {
+        commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Text {
+                    text, x, y, color, ..
+                } => Some((text.as_str(), *x, *y, *color)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn known_rows() -> Vec<DisplayRow> {
+        vec![
+            vec![Empty; 4],
+            vec![Empty, CellDisplayState::Cursor(Shape::T), Empty, Empty],
+            vec![Empty, Stack(Shape::S), Empty, Empty],
+            vec![Stack(Shape::I), Garbage, Stack(Shape::S), Stack(Shape::L)],
+        ]
+    }
+
+    #[test]
+    fn draws_every_cell_of_a_known_board() {
+        let board = small_board();
+        let theme = Theme::guideline();
+        let rows = known_rows();
+        let commands = record(&board_state(&board, rows.clone()), &theme);
+        assert_eq!(cell_fills(&commands, CELL_SIZE), board_fills(&rows, &theme));
+    }
+
+    #[test]
+    fn outlines_the_ghost_below_the_hidden_rows() {
+        let board = small_board();
+        let theme = Theme::guideline();
+        let state = BoardState {
+            ghost_cursor_positions: vec![Position { x: 2, y: 4 }],
+            ..board_state(&board, known_rows())
+        };
+        let commands = record(&state, &theme);
+        assert!(commands.contains(&DrawCommand::Outline {
+            x: 2. * CELL_SIZE + LINE_THICKNESS / 4.,
+            y: 2. * CELL_SIZE + LINE_THICKNESS / 4.,
+            width: CELL_SIZE - LINE_THICKNESS / 2.,
+            height: CELL_SIZE - LINE_THICKNESS / 2.,
+            thickness: LINE_THICKNESS,
+            color: theme.ghost,
+        }));
+    }
+
+    #[test]
+    fn centers_the_next_piece_under_its_label() {
+        let board = small_board();
+        let theme = Theme::guideline();
+        let state = BoardState {
+            next_pieces: vec![Piece::new(Shape::O)],
+            ..board_state(&board, vec![vec![Empty; 4]; 16])
+        };
+        let commands = record(&state, &theme);
+
+        // The panel starts a column to the right of the board, half a cell down.
+        let screen_size = SizeInPixels {
+            width: SCREEN_SIZE.width,
+            height: 16. * CELL_SIZE,
+        };
+        let font_size =
+            calc_layout(&screen_size, 1.0, 4, 16, 1.0, false).font_size * SIDE_PANEL_FONT_SCALE;
+        let (label, x, _, color) = texts(&commands)[0];
+        assert_eq!((label, x, color), ("Next", 5. * CELL_SIZE, theme.text));
+
+        // The O is two cells wide, so it sits one cell in from each side of the box.
+        let top = CELL_SIZE / 2. + font_size * 1.25;
+        let fill = theme.active(Shape::O).fill;
+        let preview_fills: Vec<_> = cell_fills(&commands, CELL_SIZE)
+            .into_iter()
+            .filter(|&(_, _, color)| color == fill)
+            .collect();
+        assert_eq!(
+            preview_fills,
+            vec![
+                (60.0, top, fill),
+                (60.0, top + CELL_SIZE, fill),
+                (70.0, top, fill),
+                (70.0, top + CELL_SIZE, fill),
+            ]
+        );
+    }
+
+    #[test]
+    fn greys_out_the_held_piece_once_hold_is_used() {
+        let board = small_board();
+        let theme = Theme::guideline();
+        let state = BoardState {
+            hold_enabled: true,
+            opt_held_piece: Some(Piece::new(Shape::I)),
+            hold_used: true,
+            ..board_state(&board, vec![vec![Empty; 4]; 16])
+        };
+        let commands = record(&state, &theme);
+        let (label, ..) = texts(&commands)[0];
+        assert_eq!(label, "Hold");
+        let held_fills: Vec<_> = cell_fills(&commands, CELL_SIZE)
+            .into_iter()
+            .filter(|&(x, ..)| x >= 5. * CELL_SIZE)
+            .map(|(.., color)| color)
+            .collect();
+        assert_eq!(held_fills, vec![theme.garbage.fill; 4]);
+    }
+
+    #[test]
+    fn shrinks_a_crowded_side_panel_to_fit_the_canvas() {
+        let board = small_board();
+        let theme = Theme::guideline();
+        let state = BoardState {
+            next_pieces: vec![Piece::new(Shape::I); 7],
+            hold_enabled: true,
+            opt_held_piece: Some(Piece::new(Shape::T)),
+            ..board_state(&board, vec![vec![Empty; 4]; 4])
+        };
+        let commands = record(&state, &theme);
+
+        let bottoms = commands.iter().filter_map(|command| match *command {
+            DrawCommand::Rect { y, height, .. } => Some(y + height),
+            DrawCommand::Text { y, .. } => Some(y),
+            _ => None,
+        });
+        let canvas_height = 4. * CELL_SIZE;
+        for bottom in bottoms {
+            assert!(bottom <= canvas_height, "{bottom} is below the canvas");
+        }
+        // The previews are all still drawn, only smaller.
+        let fill = theme.active(Shape::I).fill;
+        let num_preview_fills = commands
+            .iter()
+            .filter(|command| matches!(command, DrawCommand::Rect { color, .. } if *color == fill))
+            .count();
+        assert_eq!(num_preview_fills, 7 * 4);
+    }
+
+    #[test]
+    fn centers_the_lines_of_the_game_over_screen() {
+        let board = small_board();
+        let screen_size = SizeInPixels {
+            width: 400.0,
+            height: 400.0,
+        };
+        let theme = Theme::guideline();
+        let mut renderer = Renderer::new(
+            theme.clone(),
+            1.0,
+            false,
+            RecordingBackend::new(screen_size.clone()),
+        );
+        let summary_lines = [String::from("Lines: 0")];
+        renderer.draw_game_over_screen(&board, "Game Over", &summary_lines, None);
+        let commands = renderer.backend.commands();
+
+        assert_eq!(commands[0], DrawCommand::Clear(theme.background));
+        let texts = texts(commands);
+        let lines = end_screen_lines(&board, "Game Over", &summary_lines, None);
+        assert_eq!(
+            texts.iter().map(|&(text, ..)| text).collect::<Vec<_>>(),
+            lines
+        );
+        for window in texts.windows(2) {
+            assert!(window[0].2 < window[1].2, "lines go down the screen");
+        }
+        let font_size = renderer.layout(4, 4).font_size;
+        for &(text, x, _, color) in &texts {
+            let width = renderer.backend.measure(text, font_size).width;
+            assert!((x + width / 2. - screen_size.width / 2.).abs() < 1e-3);
+            assert_eq!(color, theme.text);
+        }
+    }
+
+    #[test]
+    fn draws_the_victory_screen_in_the_theme_colors() {
+        let board = small_board();
+        let theme = Theme::nes();
+        let mut renderer = Renderer::new(
+            theme.clone(),
+            1.0,
+            false,
+            RecordingBackend::new(SizeInPixels {
+                width: 400.0,
+                height: 400.0,
+            }),
+        );
+        renderer.draw_victory_screen(&board, "Victory", &[], None);
+        let commands = renderer.backend.commands();
+
+        assert_eq!(commands[0], DrawCommand::Clear(theme.background));
+        let texts = texts(commands);
+        assert!(!texts.is_empty());
+        for &(.., color) in &texts {
+            assert_eq!(color, theme.accent);
+        }
+    }
+
+    #[test]
+    fn plays_the_row_removal_animation_one_frame_at_a_time() {
+        let mut board = small_board();
+        let theme = Theme::guideline();
+        let mut rows = vec![vec![Empty; 4]; 4];
+        rows[3] = vec![Stack(Shape::I); 4];
+        let mut state = board_state(&board, rows.clone());
+        state.latest_visible_rows = get_board_state(&board, state.hud.clone()).latest_visible_rows;
+        let latest_rows = state.latest_visible_rows.clone();
+        let hud = state.hud.clone();
+
+        let mut renderer = Renderer::new(
+            theme.clone(),
+            1.0,
+            false,
+            RecordingBackend::new(SCREEN_SIZE),
+        );
+        renderer.initialize_row_removal_animation(state, 2);
+        let start = Instant::now();
+        let mut fills_at = |renderer: &mut Renderer<RecordingBackend>, ms| {
+            renderer.draw_at(&mut board, hud.clone(), start + Duration::from_millis(ms));
+            // The real board's next pieces are drawn once the animation is over.
+            let mut fills = cell_fills(&renderer.backend.take_commands(), CELL_SIZE);
+            fills.retain(|&(x, ..)| x < 4. * CELL_SIZE);
+            fills
+        };
+
+        // The full row stays as it was until the first frame has been up long enough.
+        assert_eq!(fills_at(&mut renderer, 0), board_fills(&rows, &theme));
+        assert_eq!(fills_at(&mut renderer, 30), board_fills(&rows, &theme));
+        assert!(renderer.drawing_row_removal_animation());
+
+        // Then the row empties from the middle out.
+        rows[3] = vec![BeingRemoved, Empty, Empty, BeingRemoved];
+        assert_eq!(fills_at(&mut renderer, 60), board_fills(&rows, &theme));
+        assert!(renderer.drawing_row_removal_animation());
+
+        // Once the last frame has been up long enough, the board is drawn as it is now.
+        assert_eq!(
+            fills_at(&mut renderer, 120),
+            board_fills(&latest_rows, &theme)
+        );
+        assert!(!renderer.drawing_row_removal_animation());
+    }
+
+    #[test]
+    fn renders_a_png_of_the_board_and_side_panel() {
+        let board = small_board();
+        let theme = Theme::guideline();
+        let dir = std::env::temp_dir().join(format!("quads-render-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("board.png");
+        render_to_png(&board, &theme, path.to_str().unwrap()).unwrap();
+
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (width, height) = (
+            (4 + NUM_SIDE_PANEL_COLS) * SNAPSHOT_CELL_SIZE,
+            4 * SNAPSHOT_CELL_SIZE,
+        );
+        assert_eq!((info.width, info.height), (width as u32, height as u32));
+        assert_eq!(
+            (info.color_type, info.bit_depth),
+            (png::ColorType::Rgba, png::BitDepth::Eight)
+        );
+        let pixel = |x: usize, y: usize| {
+            let i = (y * width + x) * 4;
+            Color::from_rgba(pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3])
+        };
+        // Nothing has locked yet, so the middle of the bottom left cell is empty,
+        // and the bottom right corner of the side panel is bare background.
+        let middle = SNAPSHOT_CELL_SIZE / 2;
+        assert_eq!(pixel(middle, height - middle), theme.empty.fill);
+        assert_eq!(pixel(width - 1, height - 1), theme.background);
+    }
+}
@@ -0,0 +1,363 @@
+use super::super::theme::Texture;
+use super::SizeInPixels;
+use macroquad::color::Color;
+use macroquad::math::Vec2;
+use macroquad::prelude::{
+    clear_background, draw_circle, draw_line, draw_rectangle, draw_rectangle_lines, draw_text,
+    draw_texture_ex, draw_triangle_lines, measure_text, screen_dpi_scale, screen_height,
+    screen_width, DrawTextureParams, FilterMode, TextDimensions, Texture2D,
+};
+use std::sync::Arc;
+
+// The primitives the renderer draws with, so that a frame can be drawn somewhere
+// other than the window.
+pub trait RenderBackend {
+    fn clear(&mut self, color: Color);
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color);
+    fn outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Color);
+    fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Color);
+    fn triangle_outline(&mut self, corners: [Vec2; 3], thickness: f32, color: Color);
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color);
+    // Stretches the texture over the rectangle, multiplying its colors by `tint`.
+    fn texture(&mut self, texture: &Texture, x: f32, y: f32, width: f32, height: f32, tint: Color);
+    // `y` is the baseline of the text.
+    fn text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color);
+    fn measure(&self, text: &str, font_size: f32) -> TextDimensions;
+    fn screen_size(&self) -> SizeInPixels;
+    // Physical pixels per unit of the coordinates drawn with.
+    fn dpi_scale(&self) -> f32 {
+        1.0
+    }
+}
+
+// Draws to the window.
+#[derive(Default)]
+pub struct MacroquadBackend {
+    // The texture last uploaded to the GPU, with the pixels it was made from.
+    opt_uploaded_texture: Option<(Arc<[u8]>, Texture2D)>,
+}
+
+impl RenderBackend for MacroquadBackend {
+    fn clear(&mut self, color: Color) {
+        clear_background(color);
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        draw_rectangle(x, y, width, height, color);
+    }
+
+    fn outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Color) {
+        draw_rectangle_lines(x, y, width, height, thickness, color);
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Color) {
+        draw_line(from.x, from.y, to.x, to.y, thickness, color);
+    }
+
+    fn triangle_outline(&mut self, corners: [Vec2; 3], thickness: f32, color: Color) {
+        draw_triangle_lines(corners[0], corners[1], corners[2], thickness, color);
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        draw_circle(center.x, center.y, radius, color);
+    }
+
+    fn texture(&mut self, texture: &Texture, x: f32, y: f32, width: f32, height: f32, tint: Color) {
+        let is_uploaded = matches!(
+            &self.opt_uploaded_texture,
+            Some((pixels, _)) if Arc::ptr_eq(pixels, &texture.pixels)
+        );
+        if !is_uploaded {
+            #[allow(clippy::cast_possible_truncation)]
+            let texture_2d =
+                Texture2D::from_rgba8(texture.width as u16, texture.height as u16, &texture.pixels);
+            texture_2d.set_filter(FilterMode::Nearest);
+            self.opt_uploaded_texture = Some((Arc::clone(&texture.pixels), texture_2d));
+        }
+        if let Some((_, texture_2d)) = &self.opt_uploaded_texture {
+            let params = DrawTextureParams {
+                dest_size: Some(Vec2::new(width, height)),
+                ..Default::default()
+            };
+            draw_texture_ex(texture_2d, x, y, tint, params);
+        }
+    }
+
+    fn text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        draw_text(text, x, y, font_size, color);
+    }
+
+    fn measure(&self, text: &str, font_size: f32) -> TextDimensions {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        measure_text(text, None, font_size as u16, 1.0)
+    }
+
+    fn screen_size(&self) -> SizeInPixels {
+        SizeInPixels {
+            width: screen_width(),
+            height: screen_height(),
+        }
+    }
+
+    fn dpi_scale(&self) -> f32 {
+        screen_dpi_scale()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Clear(Color),
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Color,
+    },
+    Outline {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        thickness: f32,
+        color: Color,
+    },
+    Line {
+        from: Vec2,
+        to: Vec2,
+        thickness: f32,
+        color: Color,
+    },
+    TriangleOutline {
+        corners: [Vec2; 3],
+        thickness: f32,
+        color: Color,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+        color: Color,
+    },
+    Texture {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        tint: Color,
+    },
+    Text {
+        text: String,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    },
+}
+
+// Keeps the commands of a frame instead of drawing them, for inspecting a layout
+// without a window. Text is measured with fixed-width glyphs so that the result
+// doesn't depend on a font.
+pub struct RecordingBackend {
+    screen_size: SizeInPixels,
+    commands: Vec<DrawCommand>,
+}
+
+impl RecordingBackend {
+    const GLYPH_WIDTH: f32 = 0.5;
+    const GLYPH_HEIGHT: f32 = 0.7;
+
+    pub fn new(screen_size: SizeInPixels) -> Self {
+        RecordingBackend {
+            screen_size,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    pub fn take_commands(&mut self) -> Vec<DrawCommand> {
+        std::mem::take(&mut self.commands)
+    }
+}
+
+impl RenderBackend for RecordingBackend {
+    fn clear(&mut self, color: Color) {
+        self.commands.push(DrawCommand::Clear(color));
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.commands.push(DrawCommand::Rect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    fn outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Color) {
+        self.commands.push(DrawCommand::Outline {
+            x,
+            y,
+            width,
+            height,
+            thickness,
+            color,
+        });
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Color) {
+        self.commands.push(DrawCommand::Line {
+            from,
+            to,
+            thickness,
+            color,
+        });
+    }
+
+    fn triangle_outline(&mut self, corners: [Vec2; 3], thickness: f32, color: Color) {
+        self.commands.push(DrawCommand::TriangleOutline {
+            corners,
+            thickness,
+            color,
+        });
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.commands.push(DrawCommand::Circle {
+            center,
+            radius,
+            color,
+        });
+    }
+
+    fn texture(
+        &mut self,
+        _texture: &Texture,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        tint: Color,
+    ) {
+        self.commands.push(DrawCommand::Texture {
+            x,
+            y,
+            width,
+            height,
+            tint,
+        });
+    }
+
+    fn text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        self.commands.push(DrawCommand::Text {
+            text: String::from(text),
+            x,
+            y,
+            font_size,
+            color,
+        });
+    }
+
+    fn measure(&self, text: &str, font_size: f32) -> TextDimensions {
+        let height = font_size * Self::GLYPH_HEIGHT;
+        TextDimensions {
+            width: text.chars().count() as f32 * font_size * Self::GLYPH_WIDTH,
+            height,
+            offset_y: height,
+        }
+    }
+
+    fn screen_size(&self) -> SizeInPixels {
+        self.screen_size.clone()
+    }
+}
+
+// Draws into a region of another backend as if the region were the whole screen.
+pub struct Viewport<'a, B: RenderBackend> {
+    backend: &'a mut B,
+    origin: Vec2,
+    size: SizeInPixels,
+}
+
+impl<'a, B: RenderBackend> Viewport<'a, B> {
+    pub fn new(backend: &'a mut B, origin: Vec2, size: SizeInPixels) -> Self {
+        Viewport {
+            backend,
+            origin,
+            size,
+        }
+    }
+}
+
+impl<B: RenderBackend> RenderBackend for Viewport<'_, B> {
+    fn clear(&mut self, color: Color) {
+        self.backend.rect(
+            self.origin.x,
+            self.origin.y,
+            self.size.width,
+            self.size.height,
+            color,
+        );
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.backend
+            .rect(self.origin.x + x, self.origin.y + y, width, height, color);
+    }
+
+    fn outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Color) {
+        self.backend.outline(
+            self.origin.x + x,
+            self.origin.y + y,
+            width,
+            height,
+            thickness,
+            color,
+        );
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Color) {
+        self.backend
+            .line(self.origin + from, self.origin + to, thickness, color);
+    }
+
+    fn triangle_outline(&mut self, corners: [Vec2; 3], thickness: f32, color: Color) {
+        self.backend
+            .triangle_outline(corners.map(|corner| self.origin + corner), thickness, color);
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.backend.circle(self.origin + center, radius, color);
+    }
+
+    fn texture(&mut self, texture: &Texture, x: f32, y: f32, width: f32, height: f32, tint: Color) {
+        self.backend.texture(
+            texture,
+            self.origin.x + x,
+            self.origin.y + y,
+            width,
+            height,
+            tint,
+        );
+    }
+
+    fn text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        self.backend
+            .text(text, self.origin.x + x, self.origin.y + y, font_size, color);
+    }
+
+    fn measure(&self, text: &str, font_size: f32) -> TextDimensions {
+        self.backend.measure(text, font_size)
+    }
+
+    fn screen_size(&self) -> SizeInPixels {
+        self.size.clone()
+    }
+
+    fn dpi_scale(&self) -> f32 {
+        self.backend.dpi_scale()
+    }
+}
@@ -0,0 +1,242 @@
+use super::super::theme::Texture;
+use super::backend::RenderBackend;
+use super::SizeInPixels;
+use fontdue::{Font, FontSettings};
+use macroquad::color::Color;
+use macroquad::math::Vec2;
+use macroquad::prelude::TextDimensions;
+use std::fs::File;
+use std::io::BufWriter;
+
+// The font macroquad draws text with by default, so that text lands where it
+// does in the window.
+const FONT_BYTES: &[u8] = include_bytes!("../../assets/ProggyClean.ttf");
+
+// Draws into an RGBA image in memory, without a window or GPU. A pixel is covered
+// by a shape when its center is; only text is anti-aliased.
+pub struct RasterBackend {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    font: Font,
+}
+
+impl RasterBackend {
+    pub fn new(width: usize, height: usize) -> Self {
+        RasterBackend {
+            width,
+            height,
+            pixels: vec![Color::new(0.0, 0.0, 0.0, 1.0); width * height],
+            font: Font::from_bytes(FONT_BYTES, FontSettings::default())
+                .expect("the bundled font is valid"),
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    pub fn save_png(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        #[allow(clippy::cast_possible_truncation)]
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let bytes: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b, color.a])
+            .map(|channel| (channel * 255.).round() as u8)
+            .collect();
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&bytes))
+            .map_err(|e| e.to_string())
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: Color, coverage: f32) {
+        let alpha = color.a * coverage;
+        let pixel = &mut self.pixels[y * self.width + x];
+        pixel.r += (color.r - pixel.r) * alpha;
+        pixel.g += (color.g - pixel.g) * alpha;
+        pixel.b += (color.b - pixel.b) * alpha;
+    }
+
+    // Blends every pixel in the box whose center passes the test.
+    fn fill_where(
+        &mut self,
+        (left, top, right, bottom): (f32, f32, f32, f32),
+        color: Color,
+        covers: impl Fn(Vec2) -> bool,
+    ) {
+        let (x_range, y_range) = (
+            self.pixel_range(left, right, self.width),
+            self.pixel_range(top, bottom, self.height),
+        );
+        for y in y_range {
+            for x in x_range.clone() {
+                if covers(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) {
+                    self.blend(x, y, color, 1.0);
+                }
+            }
+        }
+    }
+
+    // The pixels whose centers lie in `from..to`, clipped to the image.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn pixel_range(&self, from: f32, to: f32, len: usize) -> std::ops::Range<usize> {
+        let first = (from - 0.5).ceil().max(0.0) as usize;
+        let end = ((to - 0.5).ceil().max(0.0) as usize).min(len);
+        first..end.max(first)
+    }
+}
+
+impl RenderBackend for RasterBackend {
+    fn clear(&mut self, color: Color) {
+        self.pixels.fill(Color { a: 1.0, ..color });
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.fill_where((x, y, x + width, y + height), color, |_| true);
+    }
+
+    // Like macroquad, the lines are drawn inside the rectangle.
+    fn outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Color) {
+        self.rect(x, y, width, thickness, color);
+        self.rect(x, y + height - thickness, width, thickness, color);
+        self.rect(x, y + thickness, thickness, height - 2. * thickness, color);
+        self.rect(
+            x + width - thickness,
+            y + thickness,
+            thickness,
+            height - 2. * thickness,
+            color,
+        );
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Color) {
+        let half_thickness = thickness / 2.;
+        let bounds = (
+            from.x.min(to.x) - half_thickness,
+            from.y.min(to.y) - half_thickness,
+            from.x.max(to.x) + half_thickness,
+            from.y.max(to.y) + half_thickness,
+        );
+        let direction = to - from;
+        let length_squared = direction.length_squared().max(f32::EPSILON);
+        self.fill_where(bounds, color, |point| {
+            let t = ((point - from).dot(direction) / length_squared).clamp(0.0, 1.0);
+            point.distance(from + direction * t) <= half_thickness
+        });
+    }
+
+    fn triangle_outline(&mut self, corners: [Vec2; 3], thickness: f32, color: Color) {
+        for i in 0..3 {
+            self.line(corners[i], corners[(i + 1) % 3], thickness, color);
+        }
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let bounds = (
+            center.x - radius,
+            center.y - radius,
+            center.x + radius,
+            center.y + radius,
+        );
+        self.fill_where(bounds, color, |point| point.distance(center) <= radius);
+    }
+
+    // Samples the texel under each pixel's center, without filtering.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn texture(&mut self, texture: &Texture, x: f32, y: f32, width: f32, height: f32, tint: Color) {
+        if texture.width == 0 || texture.height == 0 {
+            return;
+        }
+        let (x_range, y_range) = (
+            self.pixel_range(x, x + width, self.width),
+            self.pixel_range(y, y + height, self.height),
+        );
+        for py in y_range {
+            let v = (py as f32 + 0.5 - y) / height;
+            let texel_y = ((v * texture.height as f32) as usize).min(texture.height - 1);
+            for px in x_range.clone() {
+                let u = (px as f32 + 0.5 - x) / width;
+                let texel_x = ((u * texture.width as f32) as usize).min(texture.width - 1);
+                let texel = texture.pixel(texel_x, texel_y);
+                let color = Color::new(
+                    texel.r * tint.r,
+                    texel.g * tint.g,
+                    texel.b * tint.b,
+                    texel.a * tint.a,
+                );
+                self.blend(px, py, color, 1.0);
+            }
+        }
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_sign_loss
+    )]
+    fn text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        let mut pen_x = x;
+        for character in text.chars() {
+            let (metrics, coverage) = self.font.rasterize(character, font_size);
+            let left = pen_x.round() as i32 + metrics.xmin;
+            let top = y.round() as i32 - metrics.ymin - metrics.height as i32;
+            for (i, &glyph_coverage) in coverage.iter().enumerate() {
+                let px = left + (i % metrics.width) as i32;
+                let py = top + (i / metrics.width) as i32;
+                if glyph_coverage > 0
+                    && (0..self.width as i32).contains(&px)
+                    && (0..self.height as i32).contains(&py)
+                {
+                    self.blend(
+                        px as usize,
+                        py as usize,
+                        color,
+                        f32::from(glyph_coverage) / 255.,
+                    );
+                }
+            }
+            pen_x += metrics.advance_width;
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn measure(&self, text: &str, font_size: f32) -> TextDimensions {
+        if text.is_empty() {
+            return TextDimensions {
+                width: 0.0,
+                height: 0.0,
+                offset_y: 0.0,
+            };
+        }
+        let mut width = 0.0;
+        let mut min_y = f32::MAX;
+        let mut max_y = f32::MIN;
+        for character in text.chars() {
+            let metrics = self.font.metrics(character, font_size);
+            let offset_y = -(metrics.ymin as f32) - metrics.height as f32;
+            width += metrics.advance_width;
+            min_y = min_y.min(offset_y);
+            max_y = max_y.max(metrics.height as f32 + offset_y);
+        }
+        TextDimensions {
+            width,
+            height: max_y - min_y,
+            offset_y: max_y,
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn screen_size(&self) -> SizeInPixels {
+        SizeInPixels {
+            width: self.width as f32,
+            height: self.height as f32,
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use super::board::{Board, BoardConfig};
+use super::game_mode::{GameMode, GameResult, ModeConfig};
+use super::hud::Hud;
+use super::input::{Input, KeyboardState};
+use super::leaderboard::{Leaderboard, Rank};
+use super::ruleset::RulesetOverrides;
+use super::tetromino_move::TetrominoMove;
+use super::user_action::UserAction;
+use super::user_move::UserMove;
+use macroquad::input::KeyCode;
+use std::time::Instant;
+
+// The state of a game in progress, advanced one frame at a time by a frontend
+// that reads the keyboard and draws the board.
+pub struct GameParams {
+    mode_config: ModeConfig,
+    board_config: BoardConfig,
+    ruleset_overrides: RulesetOverrides,
+    pub mode: Box<dyn GameMode>,
+    start_time: Instant,
+    leaderboard: Option<Leaderboard>,
+    leaderboard_rank: Option<Rank>,
+    last_down_move_time: Instant,
+    entry_delay_end_time: Instant,
+    opt_grounded_since: Option<Instant>,
+    // A firm drop restarts the lock delay only once per piece.
+    firm_drop_used: bool,
+    // Set when a piece spawns, until initial rotation and hold have been applied to it.
+    initial_moves_pending: bool,
+    input: Input,
+    pub board: Board,
+    pub game_over: bool,
+    pub exit_game: bool,
+}
+
+pub fn initialize_game(
+    mode_config: ModeConfig,
+    board_config: BoardConfig,
+    ruleset_overrides: RulesetOverrides,
+) -> GameParams {
+    let now = Instant::now();
+    let mut mode = mode_config.create();
+    let leaderboard = mode.leaderboard_filename().map(Leaderboard::load);
+    let last_down_move_time = now;
+    let mut ruleset = mode.ruleset();
+    ruleset_overrides.apply_to(&mut ruleset);
+    let input = Input::new(&ruleset);
+    let mut board = Board::new(ruleset, board_config);
+    board.load_high_score(mode.high_score_filename());
+    mode.on_start(&mut board);
+    let game_over = false;
+    let exit_game = false;
+    GameParams {
+        mode_config,
+        board_config,
+        ruleset_overrides,
+        mode,
+        start_time: now,
+        leaderboard,
+        leaderboard_rank: None,
+        last_down_move_time,
+        entry_delay_end_time: now,
+        opt_grounded_since: None,
+        firm_drop_used: false,
+        initial_moves_pending: true,
+        input,
+        board,
+        game_over,
+        exit_game,
+    }
+}
+
+impl GameParams {
+    // Plays one frame: input, gravity and locking, then the mode's own rules.
+    pub fn step(&mut self, keys: &KeyboardState, now: Instant) {
+        run_game_step(self, keys, now);
+
+        self.mode
+            .on_tick(&mut self.board, self.start_time.elapsed());
+
+        if self.mode.is_finished() {
+            self.game_over = true;
+        }
+
+        if self.game_over {
+            self.board.save_high_score();
+            self.submit_score_to_leaderboard();
+        }
+    }
+
+    pub fn hud(&self) -> Hud {
+        Hud {
+            opt_level: self.mode.opt_level(),
+            status_lines: self.mode.status_lines(),
+            ..Hud::new(&self.board, self.start_time.elapsed())
+        }
+    }
+
+    // Keeps gravity from moving the piece while the frontend holds the game, for
+    // example to animate cleared rows.
+    pub fn hold_gravity(&mut self, now: Instant) {
+        self.last_down_move_time = now;
+    }
+
+    // Returns true when the move locked the piece.
+    fn apply_move(&mut self, tetromino_move: TetrominoMove, now: Instant) -> bool {
+        match tetromino_move {
+            TetrominoMove::UM(UserMove::Hold) => {
+                self.opt_grounded_since = None;
+                self.firm_drop_used = false;
+            }
+            TetrominoMove::UM(UserMove::FirmDrop) if !self.firm_drop_used => {
+                self.opt_grounded_since = None;
+                self.firm_drop_used = true;
+            }
+            _ => (),
+        }
+        let Some(docking_outcome) = self.board.update(tetromino_move) else {
+            return false;
+        };
+
+        self.entry_delay_end_time = now
+            + self.mode.entry_delay().after_lock(
+                docking_outcome.lock_height,
+                docking_outcome.num_rows_cleared,
+            );
+        self.opt_grounded_since = None;
+        self.firm_drop_used = false;
+        self.initial_moves_pending = true;
+        self.mode.on_lock(docking_outcome.opt_top_out);
+        if docking_outcome.num_rows_cleared > 0 {
+            self.mode.on_lines_cleared(docking_outcome.num_rows_cleared);
+        }
+        true
+    }
+
+    // Initial rotation and hold (IRS/IHS). A rotation that does not fit, even
+    // with kicks, leaves the piece in its spawn orientation. Returns the moves
+    // applied.
+    fn apply_initial_moves(&mut self, keys: &KeyboardState, now: Instant) -> Vec<UserMove> {
+        if !self.board.ruleset().irs_ihs_enabled {
+            return Vec::new();
+        }
+        let initial_moves = self.input.held_initial_moves(keys);
+        for &user_move in &initial_moves {
+            self.apply_move(TetrominoMove::UM(user_move), now);
+        }
+        initial_moves
+    }
+
+    // Moves the piece down by as many rows as are due since the last down move,
+    // which at high gravity (up to 20G) can be several rows in a single frame.
+    // Holding soft drop speeds this up while the piece is in the air.
+    // Returns true when gravity or soft drop locked the piece.
+    fn apply_gravity(&mut self, keys: &KeyboardState, now: Instant) -> bool {
+        let ruleset = *self.board.ruleset();
+        let grounded = self.board.is_grounded();
+        let soft_drop_held = self.input.soft_drop_held(keys);
+        if soft_drop_held && grounded && ruleset.soft_drop_locks_on_ground {
+            return self.apply_move(TetrominoMove::UM(UserMove::SoftDown), now);
+        }
+
+        let soft_dropping = soft_drop_held && !grounded;
+        let (auto_drop_interval, down_move) = if soft_dropping {
+            (
+                ruleset
+                    .soft_drop_speed
+                    .interval(self.mode.auto_drop_interval()),
+                TetrominoMove::UM(UserMove::SoftDown),
+            )
+        } else {
+            (self.mode.auto_drop_interval(), TetrominoMove::AutoDown)
+        };
+        let elapsed = now - self.last_down_move_time;
+        if elapsed <= auto_drop_interval && !auto_drop_interval.is_zero() {
+            return false;
+        }
+
+        let max_num_rows = self.board.num_total_rows();
+        let num_rows_due = elapsed
+            .as_nanos()
+            .checked_div(auto_drop_interval.as_nanos())
+            .map_or(max_num_rows, |num_rows| {
+                usize::try_from(num_rows).unwrap_or(max_num_rows)
+            });
+        if num_rows_due >= max_num_rows {
+            self.last_down_move_time = now;
+        } else {
+            #[allow(clippy::cast_possible_truncation)]
+            let num_rows = num_rows_due as u32;
+            self.last_down_move_time += auto_drop_interval * num_rows;
+        }
+
+        let lock_delay_enabled = self.mode.lock_delay().is_some();
+        for _ in 0..num_rows_due.min(max_num_rows) {
+            if lock_delay_enabled && self.board.is_grounded() {
+                break;
+            }
+            if self.apply_move(down_move, now) {
+                return true;
+            }
+        }
+        false
+    }
+
+    // Locks a grounded piece once it has rested for the lock delay.
+    // The delay starts over whenever the piece leaves the ground.
+    fn apply_lock_delay(&mut self, now: Instant) {
+        let Some(lock_delay) = self.mode.lock_delay() else {
+            return;
+        };
+
+        if !self.board.is_grounded() {
+            self.opt_grounded_since = None;
+            return;
+        }
+
+        let grounded_since = *self.opt_grounded_since.get_or_insert(now);
+        if now - grounded_since >= lock_delay {
+            self.apply_move(TetrominoMove::AutoDown, now);
+        }
+    }
+
+    // Only a completed run counts, not one that topped out or was quit early.
+    fn submit_score_to_leaderboard(&mut self) {
+        if self.mode.result() != GameResult::Completed {
+            return;
+        }
+        if let Some(leaderboard) = self.leaderboard.as_mut() {
+            self.leaderboard_rank = leaderboard.submit(self.board.score());
+        }
+    }
+
+    pub fn leaderboard_result(&self) -> Option<(&Leaderboard, Option<Rank>)> {
+        self.leaderboard
+            .as_ref()
+            .map(|leaderboard| (leaderboard, self.leaderboard_rank))
+    }
+}
+
+pub fn reset_or_quit_game_when_apt(gp: &mut GameParams, keys: &KeyboardState) {
+    if keys.is_pressed(KeyCode::Enter) {
+        *gp = initialize_game(gp.mode_config, gp.board_config, gp.ruleset_overrides);
+    } else if keys.is_pressed(KeyCode::Q) {
+        gp.exit_game = true;
+    }
+}
+
+fn run_game_step(gp: &mut GameParams, keys: &KeyboardState, now: Instant) {
+    gp.board.advance_tick();
+
+    if now < gp.entry_delay_end_time {
+        // Neither gravity nor the player can move the piece until the entry delay is over.
+        gp.last_down_move_time = now;
+        return;
+    }
+
+    let initial_moves = if gp.initial_moves_pending {
+        gp.initial_moves_pending = false;
+        gp.apply_initial_moves(keys, now)
+    } else {
+        Vec::new()
+    };
+    // Keys held at spawn time have already acted on the new piece; any other key
+    // pressed this frame still counts.
+    let opt_user_action = gp.input.get_user_action(keys, now).filter(
+        |action| !matches!(action, UserAction::UM(user_move) if initial_moves.contains(user_move)),
+    );
+
+    match opt_user_action {
+        Some(UserAction::Quit) => {
+            gp.game_over = true;
+            return;
+        }
+        Some(UserAction::UM(user_move)) => {
+            let tet_move = TetrominoMove::UM(user_move);
+            if tet_move.resets_down_timer() {
+                gp.last_down_move_time = now;
+            }
+            if gp.apply_move(tet_move, now) {
+                return;
+            }
+        }
+        None => (),
+    }
+
+    if gp.apply_gravity(keys, now) {
+        return;
+    }
+
+    gp.apply_lock_delay(now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classic_game(irs_ihs_enabled: bool) -> GameParams {
+        let overrides = RulesetOverrides {
+            opt_irs_ihs_enabled: Some(irs_ihs_enabled),
+            ..RulesetOverrides::default()
+        };
+        initialize_game(ModeConfig::Classic, BoardConfig::default(), overrides)
+    }
+
+    fn press(key: KeyCode) -> KeyboardState {
+        KeyboardState {
+            pressed: vec![key],
+            down: vec![key],
+        }
+    }
+
+    #[test]
+    fn a_hard_drop_on_the_spawn_frame_locks_the_piece() {
+        for irs_ihs_enabled in [false, true] {
+            let mut gp = classic_game(irs_ihs_enabled);
+            gp.step(&press(KeyCode::Space), Instant::now());
+            assert_eq!(gp.board.num_pieces_locked(), 1, "IRS/IHS {irs_ihs_enabled}");
+        }
+    }
+}
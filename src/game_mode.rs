@@ -0,0 +1,251 @@
+pub mod classic;
+pub mod dig;
+pub mod garbage;
+pub mod marathon;
+pub mod master;
+pub mod nes;
+pub mod survival;
+pub mod ultra;
+
+use super::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use super::ruleset::{EntryDelay, Ruleset};
+use classic::Classic;
+use dig::Dig;
+use marathon::Marathon;
+use master::Master;
+use nes::Nes;
+use std::time::Duration;
+use survival::Survival;
+use ultra::Ultra;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameResult {
+    InProgress,
+    ToppedOut(TopOut),
+    Completed,
+}
+
+impl GameResult {
+    pub fn headline(self) -> String {
+        match self {
+            GameResult::InProgress => String::from("Game Over"),
+            GameResult::ToppedOut(top_out) => format!("Game Over: {}", top_out.name()),
+            GameResult::Completed => String::from("Well Done!"),
+        }
+    }
+}
+
+// The rules of a game: how fast pieces fall and what ends the game.
+// The hooks are called by the game loop; `elapsed` is the time since the game started.
+pub trait GameMode {
+    fn name(&self) -> &'static str;
+
+    fn ruleset(&self) -> Ruleset {
+        Ruleset::modern()
+    }
+
+    // Modes whose timing changes as the game progresses override these.
+    fn entry_delay(&self) -> EntryDelay {
+        self.ruleset().entry_delay
+    }
+
+    fn lock_delay(&self) -> Option<Duration> {
+        self.ruleset().lock_delay
+    }
+
+    fn on_start(&mut self, _board: &mut Board) {}
+
+    fn on_tick(&mut self, _board: &mut Board, _elapsed: Duration) {}
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>);
+
+    fn on_lines_cleared(&mut self, _num_lines_cleared: NumRowsClearedThisUpdate) {}
+
+    fn auto_drop_interval(&self) -> Duration;
+
+    fn is_finished(&self) -> bool;
+
+    fn result(&self) -> GameResult;
+
+    // Shown in the HUD by modes that have levels.
+    fn opt_level(&self) -> Option<usize> {
+        None
+    }
+
+    // Mode-specific text shown under the HUD during play. Score, lines and time
+    // are already part of the HUD.
+    fn status_lines(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // Mode-specific stats shown on the end-of-game screen.
+    fn summary_lines(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn leaderboard_filename(&self) -> Option<&'static str> {
+        None
+    }
+
+    // Every mode keeps a high score of its own.
+    fn high_score_filename(&self) -> &'static str;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ModeConfig {
+    Classic,
+    Ultra {
+        time_limit: Duration,
+    },
+    Marathon {
+        line_goal: usize,
+        endless: bool,
+    },
+    Dig {
+        num_garbage_rows: usize,
+        messiness: f32,
+    },
+    Survival {
+        initial_garbage_interval: Duration,
+    },
+    Nes {
+        start_level: usize,
+    },
+    Master,
+}
+
+impl ModeConfig {
+    // Accepts e.g. `ultra`, `ultra 180` (time limit in seconds),
+    // `marathon`, `marathon 200`, `marathon 150 endless`,
+    // `dig`, `dig 12 0.5` (number of garbage rows, messiness from 0 to 1),
+    // `survival`, `survival 8` (initial seconds between garbage rows),
+    // `nes`, `nes 18` (start level from 0 to 19) or `master`.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        match args.next().as_deref() {
+            None | Some("classic") => ModeConfig::Classic,
+            Some("ultra") => {
+                let time_limit = match args.next() {
+                    Some(arg) => parse_seconds(&arg).unwrap_or_else(|| {
+                        eprintln!("Invalid time limit '{arg}', using the default");
+                        ultra::DEFAULT_TIME_LIMIT
+                    }),
+                    None => ultra::DEFAULT_TIME_LIMIT,
+                };
+                ModeConfig::Ultra { time_limit }
+            }
+            Some("marathon") => {
+                let mut line_goal = marathon::DEFAULT_LINE_GOAL;
+                let mut endless = false;
+                for arg in args {
+                    if arg == "endless" {
+                        endless = true;
+                    } else if let Some(goal) = parse_positive(&arg) {
+                        line_goal = goal;
+                    } else {
+                        eprintln!("Ignoring invalid marathon option '{arg}'");
+                    }
+                }
+                ModeConfig::Marathon { line_goal, endless }
+            }
+            Some("dig") => {
+                let num_garbage_rows = match args.next() {
+                    Some(arg) => parse_positive(&arg).unwrap_or_else(|| {
+                        eprintln!("Invalid number of garbage rows '{arg}', using the default");
+                        dig::DEFAULT_NUM_GARBAGE_ROWS
+                    }),
+                    None => dig::DEFAULT_NUM_GARBAGE_ROWS,
+                };
+                let messiness = match args.next() {
+                    Some(arg) => parse_probability(&arg).unwrap_or_else(|| {
+                        eprintln!("Invalid messiness '{arg}', using the default");
+                        dig::DEFAULT_MESSINESS
+                    }),
+                    None => dig::DEFAULT_MESSINESS,
+                };
+                ModeConfig::Dig {
+                    num_garbage_rows,
+                    messiness,
+                }
+            }
+            Some("survival") => {
+                let initial_garbage_interval = match args.next() {
+                    Some(arg) => parse_seconds(&arg).unwrap_or_else(|| {
+                        eprintln!("Invalid garbage interval '{arg}', using the default");
+                        survival::DEFAULT_INITIAL_GARBAGE_INTERVAL
+                    }),
+                    None => survival::DEFAULT_INITIAL_GARBAGE_INTERVAL,
+                };
+                ModeConfig::Survival {
+                    initial_garbage_interval,
+                }
+            }
+            Some("nes") => {
+                let start_level = match args.next() {
+                    Some(arg) => parse_start_level(&arg).unwrap_or_else(|| {
+                        eprintln!("Invalid start level '{arg}', starting at level 0");
+                        0
+                    }),
+                    None => 0,
+                };
+                ModeConfig::Nes { start_level }
+            }
+            Some("master") => ModeConfig::Master,
+            Some(other) => {
+                eprintln!("Unknown game mode '{other}', playing classic");
+                ModeConfig::Classic
+            }
+        }
+    }
+
+    pub fn create(self) -> Box<dyn GameMode> {
+        match self {
+            ModeConfig::Classic => Box::new(Classic::new()),
+            ModeConfig::Ultra { time_limit } => Box::new(Ultra::new(time_limit)),
+            ModeConfig::Marathon { line_goal, endless } => {
+                Box::new(Marathon::new(line_goal, endless))
+            }
+            ModeConfig::Dig {
+                num_garbage_rows,
+                messiness,
+            } => Box::new(Dig::new(num_garbage_rows, messiness)),
+            ModeConfig::Survival {
+                initial_garbage_interval,
+            } => Box::new(Survival::new(initial_garbage_interval)),
+            ModeConfig::Nes { start_level } => Box::new(Nes::new(start_level)),
+            ModeConfig::Master => Box::new(Master::new()),
+        }
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let total_tenths = duration.as_millis() / 100;
+    let minutes = total_tenths / 600;
+    let seconds = (total_tenths / 10) % 60;
+    let tenths = total_tenths % 10;
+    format!("{minutes}:{seconds:02}.{tenths}")
+}
+
+fn parse_seconds(arg: &str) -> Option<Duration> {
+    parse_positive(arg).map(|seconds| Duration::from_secs(seconds as u64))
+}
+
+fn parse_positive(arg: &str) -> Option<usize> {
+    match arg.parse::<usize>() {
+        Ok(value) if value > 0 => Some(value),
+        _ => None,
+    }
+}
+
+fn parse_start_level(arg: &str) -> Option<usize> {
+    match arg.parse::<usize>() {
+        Ok(level) if level <= nes::MAX_START_LEVEL => Some(level),
+        _ => None,
+    }
+}
+
+fn parse_probability(arg: &str) -> Option<f32> {
+    match arg.parse::<f32>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Some(value),
+        _ => None,
+    }
+}
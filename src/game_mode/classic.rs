@@ -0,0 +1,76 @@
+use super::{GameMode, GameResult};
+use crate::board::{NumRowsClearedThisUpdate, TopOut};
+use std::time::Duration;
+
+const INITIAL_AUTO_DROP_INTERVAL: Duration = Duration::from_millis(2000);
+const INITIAL_ROW_THRESH_FOR_SPEEDUP: usize = 4;
+
+// Survive for as long as possible; the speed doubles each time the
+// number of cleared rows doubles.
+pub struct Classic {
+    auto_drop_interval: Duration,
+    total_rows_cleared: usize,
+    next_row_thresh_for_speedup: usize,
+    opt_top_out: Option<TopOut>,
+}
+
+impl Classic {
+    pub fn new() -> Self {
+        Classic {
+            auto_drop_interval: INITIAL_AUTO_DROP_INTERVAL,
+            total_rows_cleared: 0,
+            next_row_thresh_for_speedup: INITIAL_ROW_THRESH_FOR_SPEEDUP,
+            opt_top_out: None,
+        }
+    }
+}
+
+impl Default for Classic {
+    fn default() -> Self {
+        Classic::new()
+    }
+}
+
+impl GameMode for Classic {
+    fn name(&self) -> &'static str {
+        "Classic"
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.opt_top_out = self.opt_top_out.or(opt_top_out);
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.total_rows_cleared += num_lines_cleared;
+        if self.total_rows_cleared >= self.next_row_thresh_for_speedup {
+            self.next_row_thresh_for_speedup += self.next_row_thresh_for_speedup;
+            self.auto_drop_interval = scale_duration(self.auto_drop_interval, 0.5);
+        }
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        self.auto_drop_interval
+    }
+
+    fn is_finished(&self) -> bool {
+        self.opt_top_out.is_some()
+    }
+
+    fn result(&self) -> GameResult {
+        if let Some(top_out) = self.opt_top_out {
+            GameResult::ToppedOut(top_out)
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "high_score.txt"
+    }
+}
+
+fn scale_duration(duration: Duration, scale_factor: f64) -> Duration {
+    let total_millis = duration.as_millis() as f64;
+    let new_total_millis = total_millis * scale_factor;
+    Duration::from_millis(new_total_millis.round() as u64)
+}
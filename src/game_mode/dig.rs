@@ -0,0 +1,103 @@
+use super::classic::Classic;
+use super::garbage::GarbageGenerator;
+use super::{format_duration, GameMode, GameResult};
+use crate::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use std::time::Duration;
+
+pub const DEFAULT_NUM_GARBAGE_ROWS: usize = 10;
+pub const DEFAULT_MESSINESS: f32 = 0.25;
+const MIN_NUM_EMPTY_VISIBLE_ROWS: usize = 4;
+
+// Clear every garbage row as fast as possible.
+// Gravity and top-out follow the classic rules.
+pub struct Dig {
+    classic: Classic,
+    num_garbage_rows: usize,
+    garbage_generator: GarbageGenerator,
+    num_garbage_rows_left: usize,
+    play_time: Duration,
+}
+
+impl Dig {
+    pub fn new(num_garbage_rows: usize, messiness: f32) -> Self {
+        Dig {
+            classic: Classic::new(),
+            num_garbage_rows,
+            garbage_generator: GarbageGenerator::new(messiness),
+            num_garbage_rows_left: num_garbage_rows,
+            play_time: Duration::ZERO,
+        }
+    }
+
+    fn all_garbage_cleared(&self) -> bool {
+        self.num_garbage_rows_left == 0
+    }
+}
+
+impl GameMode for Dig {
+    fn name(&self) -> &'static str {
+        "Dig"
+    }
+
+    fn on_start(&mut self, board: &mut Board) {
+        // Even the shortest board gets a garbage row, or there would be nothing to dig.
+        let max_num_garbage_rows = board
+            .visible_rows()
+            .len()
+            .saturating_sub(MIN_NUM_EMPTY_VISIBLE_ROWS)
+            .max(1);
+        self.num_garbage_rows = self.num_garbage_rows.min(max_num_garbage_rows);
+        let hole_cols = self
+            .garbage_generator
+            .next_hole_cols(self.num_garbage_rows, board.num_cols());
+        let opt_top_out = board.insert_garbage_rows(&hole_cols);
+        self.classic.on_lock(opt_top_out);
+        self.num_garbage_rows_left = board.num_garbage_rows();
+    }
+
+    fn on_tick(&mut self, board: &mut Board, elapsed: Duration) {
+        self.num_garbage_rows_left = board.num_garbage_rows();
+        if !self.all_garbage_cleared() {
+            self.play_time = elapsed;
+        }
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.classic.on_lock(opt_top_out);
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.classic.on_lines_cleared(num_lines_cleared);
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        self.classic.auto_drop_interval()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.all_garbage_cleared() || self.classic.is_finished()
+    }
+
+    fn result(&self) -> GameResult {
+        if self.all_garbage_cleared() {
+            GameResult::Completed
+        } else {
+            self.classic.result()
+        }
+    }
+
+    fn status_lines(&self) -> Vec<String> {
+        vec![format!("Garbage {}", self.num_garbage_rows_left)]
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Garbage rows: {}", self.num_garbage_rows),
+            format!("Time: {}", format_duration(self.play_time)),
+        ]
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "dig_high_score.txt"
+    }
+}
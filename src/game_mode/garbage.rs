@@ -0,0 +1,37 @@
+use macroquad::prelude::rand;
+
+// Picks the hole column of each garbage row. `messiness` is the probability
+// that the hole moves to a different column than in the previous row.
+pub struct GarbageGenerator {
+    messiness: f32,
+    opt_prev_hole_col: Option<usize>,
+}
+
+impl GarbageGenerator {
+    pub fn new(messiness: f32) -> Self {
+        GarbageGenerator {
+            messiness: messiness.clamp(0.0, 1.0),
+            opt_prev_hole_col: None,
+        }
+    }
+
+    pub fn next_hole_cols(&mut self, num_rows: usize, num_cols: usize) -> Vec<usize> {
+        (0..num_rows)
+            .map(|_| self.next_hole_col(num_cols))
+            .collect()
+    }
+
+    fn next_hole_col(&mut self, num_cols: usize) -> usize {
+        let hole_col = match self.opt_prev_hole_col {
+            Some(prev_hole_col) if num_cols > 1 && rand::gen_range(0.0, 1.0) < self.messiness => {
+                // Move the hole to any column other than the previous one.
+                let offset = rand::gen_range(1, num_cols);
+                (prev_hole_col + offset) % num_cols
+            }
+            Some(prev_hole_col) => prev_hole_col,
+            None => rand::gen_range(0, num_cols),
+        };
+        self.opt_prev_hole_col = Some(hole_col);
+        hole_col
+    }
+}
@@ -0,0 +1,101 @@
+use super::{format_duration, GameMode, GameResult};
+use crate::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use std::time::Duration;
+
+pub const DEFAULT_LINE_GOAL: usize = 150;
+const NUM_LINES_PER_LEVEL: usize = 10;
+
+// Clear the line goal while the speed follows the guideline level curve.
+// In endless mode play continues past the goal until the stack tops out.
+pub struct Marathon {
+    line_goal: usize,
+    endless: bool,
+    total_rows_cleared: usize,
+    play_time: Duration,
+    opt_top_out: Option<TopOut>,
+}
+
+impl Marathon {
+    pub fn new(line_goal: usize, endless: bool) -> Self {
+        Marathon {
+            line_goal,
+            endless,
+            total_rows_cleared: 0,
+            play_time: Duration::ZERO,
+            opt_top_out: None,
+        }
+    }
+
+    fn level(&self) -> usize {
+        1 + self.total_rows_cleared / NUM_LINES_PER_LEVEL
+    }
+
+    fn goal_reached(&self) -> bool {
+        self.total_rows_cleared >= self.line_goal
+    }
+}
+
+impl GameMode for Marathon {
+    fn name(&self) -> &'static str {
+        "Marathon"
+    }
+
+    fn on_tick(&mut self, _board: &mut Board, elapsed: Duration) {
+        self.play_time = elapsed;
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.opt_top_out = self.opt_top_out.or(opt_top_out);
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.total_rows_cleared += num_lines_cleared;
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        guideline_auto_drop_interval(self.level())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.opt_top_out.is_some() || (!self.endless && self.goal_reached())
+    }
+
+    // An endless game carries on past the goal, so it can still top out after it.
+    fn result(&self) -> GameResult {
+        if let Some(top_out) = self.opt_top_out {
+            GameResult::ToppedOut(top_out)
+        } else if self.goal_reached() {
+            GameResult::Completed
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    fn opt_level(&self) -> Option<usize> {
+        Some(self.level())
+    }
+
+    fn status_lines(&self) -> Vec<String> {
+        vec![format!("Goal {} lines", self.line_goal)]
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Lines: {}", self.total_rows_cleared),
+            format!("Level: {}", self.level()),
+            format!("Time: {}", format_duration(self.play_time)),
+        ]
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "marathon_high_score.txt"
+    }
+}
+
+// Seconds per row = (0.8 - (level - 1) * 0.007) ^ (level - 1)
+fn guideline_auto_drop_interval(level: usize) -> Duration {
+    #[allow(clippy::cast_precision_loss)]
+    let level_minus_one = (level - 1) as f64;
+    let base = (0.8 - level_minus_one * 0.007).max(0.0);
+    Duration::from_secs_f64(base.powf(level_minus_one))
+}
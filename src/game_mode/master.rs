@@ -0,0 +1,354 @@
+use super::{format_duration, GameMode, GameResult};
+use crate::board::rotation_system::{Rotation180, RotationSystem};
+use crate::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use crate::ruleset::{frames, EntryDelay, KeyRepeat, Ruleset, Scoring, TopOutRules, FRAME};
+use std::time::Duration;
+
+const MAX_LEVEL: usize = 999;
+const NUM_LEVELS_PER_SECTION: usize = 100;
+
+// Gravity is measured in 1/256ths of a row per frame; 5120 is 20G.
+const GRAVITY_UNITS_PER_ROW: u32 = 256;
+
+// Reach level 999 while gravity ramps up to 20G and the delays shrink.
+// The level goes up by one per piece and by one per cleared row, but only
+// a line clear can take it past the last level of a section (x99).
+pub struct Master {
+    level: usize,
+    total_rows_cleared: usize,
+    play_time: Duration,
+    checkpoint_times: Vec<(usize, Duration)>,
+    score: i32,
+    opt_top_out: Option<TopOut>,
+}
+
+impl Master {
+    pub fn new() -> Self {
+        Master {
+            level: 0,
+            total_rows_cleared: 0,
+            play_time: Duration::ZERO,
+            checkpoint_times: Vec::new(),
+            score: 0,
+            opt_top_out: None,
+        }
+    }
+
+    fn section(&self) -> usize {
+        self.level.min(MAX_LEVEL) / NUM_LEVELS_PER_SECTION
+    }
+
+    fn section_timing(&self) -> SectionTiming {
+        SECTION_TIMINGS[self.section()]
+    }
+
+    fn reached_max_level(&self) -> bool {
+        self.level >= MAX_LEVEL
+    }
+
+    fn raise_level_to(&mut self, new_level: usize) {
+        let new_level = new_level.min(MAX_LEVEL);
+        for &(checkpoint_level, _) in GRAND_MASTER_CHECKPOINTS {
+            if self.level < checkpoint_level && new_level >= checkpoint_level {
+                self.checkpoint_times
+                    .push((checkpoint_level, self.play_time));
+            }
+        }
+        self.level = new_level;
+    }
+
+    fn grade(&self) -> &'static str {
+        if self.earned_grand_master() {
+            return "GM";
+        }
+        GRADE_SCORE_THRESHOLDS
+            .iter()
+            .rev()
+            .find(|&&(_, min_score)| self.score >= min_score)
+            .map_or("9", |&(grade, _)| grade)
+    }
+
+    // The top grade also demands speed: every checkpoint level must be
+    // reached within its time limit, and the score must be high enough.
+    fn earned_grand_master(&self) -> bool {
+        self.reached_max_level()
+            && self.score >= GRAND_MASTER_MIN_SCORE
+            && GRAND_MASTER_CHECKPOINTS
+                .iter()
+                .all(|&(checkpoint_level, time_limit)| {
+                    self.checkpoint_times
+                        .iter()
+                        .any(|&(level, time)| level == checkpoint_level && time <= time_limit)
+                })
+    }
+}
+
+impl Default for Master {
+    fn default() -> Self {
+        Master::new()
+    }
+}
+
+impl GameMode for Master {
+    fn name(&self) -> &'static str {
+        "Master"
+    }
+
+    fn ruleset(&self) -> Ruleset {
+        let timing = SECTION_TIMINGS[0];
+        Ruleset {
+            scoring: Scoring::Tgm,
+            // Without wall kicks, pieces could hardly turn at 20G.
+            rotation_system: RotationSystem::Srs,
+            key_repeat: KeyRepeat::DasArr {
+                das: frames(14),
+                arr: FRAME,
+            },
+            entry_delay: EntryDelay::fixed(frames(timing.are), frames(timing.line_clear)),
+            lock_delay: Some(frames(timing.lock_delay)),
+            top_out_rules: TopOutRules::BLOCK_OUT_ONLY,
+            irs_ihs_enabled: true,
+            soft_drop_locks_on_ground: true,
+            rotation_180: Rotation180::Disabled,
+            num_next_pieces: 1,
+            ..Ruleset::modern()
+        }
+    }
+
+    fn entry_delay(&self) -> EntryDelay {
+        let timing = self.section_timing();
+        EntryDelay::fixed(frames(timing.are), frames(timing.line_clear))
+    }
+
+    fn lock_delay(&self) -> Option<Duration> {
+        Some(frames(self.section_timing().lock_delay))
+    }
+
+    fn on_tick(&mut self, board: &mut Board, elapsed: Duration) {
+        if !self.reached_max_level() {
+            self.play_time = elapsed;
+        }
+        board.set_level(self.level);
+        self.score = board.score();
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.opt_top_out = self.opt_top_out.or(opt_top_out);
+        let at_section_stop = self.level % NUM_LEVELS_PER_SECTION == NUM_LEVELS_PER_SECTION - 1
+            || self.level == MAX_LEVEL - 1;
+        if !at_section_stop {
+            self.raise_level_to(self.level + 1);
+        }
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.total_rows_cleared += num_lines_cleared;
+        self.raise_level_to(self.level + num_lines_cleared);
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        FRAME * GRAVITY_UNITS_PER_ROW / gravity_units_per_frame(self.level)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.opt_top_out.is_some() || self.reached_max_level()
+    }
+
+    fn result(&self) -> GameResult {
+        if self.reached_max_level() {
+            GameResult::Completed
+        } else if let Some(top_out) = self.opt_top_out {
+            GameResult::ToppedOut(top_out)
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    fn status_lines(&self) -> Vec<String> {
+        let next_section_level = ((self.section() + 1) * NUM_LEVELS_PER_SECTION).min(MAX_LEVEL);
+        vec![
+            format!("Grade {}", self.grade()),
+            format!("Goal level {next_section_level}"),
+        ]
+    }
+
+    fn opt_level(&self) -> Option<usize> {
+        Some(self.level)
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Grade: {}", self.grade()),
+            format!("Level: {}", self.level),
+            format!("Lines: {}", self.total_rows_cleared),
+            format!("Time: {}", format_duration(self.play_time)),
+        ]
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "master_high_score.txt"
+    }
+}
+
+#[derive(Copy, Clone)]
+struct SectionTiming {
+    are: u32,
+    line_clear: u32,
+    lock_delay: u32,
+}
+
+// Delays in frames, one entry per section of 100 levels.
+#[rustfmt::skip]
+const SECTION_TIMINGS: [SectionTiming; 10] = [
+    SectionTiming { are: 25, line_clear: 40, lock_delay: 30 },
+    SectionTiming { are: 25, line_clear: 40, lock_delay: 30 },
+    SectionTiming { are: 25, line_clear: 40, lock_delay: 30 },
+    SectionTiming { are: 25, line_clear: 40, lock_delay: 30 },
+    SectionTiming { are: 25, line_clear: 40, lock_delay: 30 },
+    SectionTiming { are: 25, line_clear: 25, lock_delay: 30 },
+    SectionTiming { are: 16, line_clear: 16, lock_delay: 30 },
+    SectionTiming { are: 12, line_clear: 12, lock_delay: 30 },
+    SectionTiming { are: 12, line_clear:  6, lock_delay: 30 },
+    SectionTiming { are: 12, line_clear:  6, lock_delay: 17 },
+];
+
+#[rustfmt::skip]
+fn gravity_units_per_frame(level: usize) -> u32 {
+    match level {
+        0..=29    => 4,
+        30..=34   => 6,
+        35..=39   => 8,
+        40..=49   => 10,
+        50..=59   => 12,
+        60..=69   => 16,
+        70..=79   => 32,
+        80..=89   => 48,
+        90..=99   => 64,
+        100..=119 => 80,
+        120..=139 => 96,
+        140..=159 => 112,
+        160..=169 => 128,
+        170..=199 => 144,
+        200..=219 => 4,
+        220..=229 => 32,
+        230..=232 => 64,
+        233..=235 => 96,
+        236..=238 => 128,
+        239..=242 => 160,
+        243..=246 => 192,
+        247..=250 => 224,
+        251..=299 => 256,
+        300..=329 => 512,
+        330..=359 => 768,
+        360..=399 => 1024,
+        400..=419 => 1280,
+        420..=449 => 1024,
+        450..=499 => 768,
+        _         => 5120,
+    }
+}
+
+#[rustfmt::skip]
+const GRADE_SCORE_THRESHOLDS: &[(&str, i32)] = &[
+    ("9",  0),      ("8",  400),    ("7",  800),    ("6",  1400),   ("5",  2000),
+    ("4",  3500),   ("3",  5500),   ("2",  8000),   ("1",  12000),  ("S1", 16000),
+    ("S2", 22000),  ("S3", 30000),  ("S4", 40000),  ("S5", 52000),  ("S6", 66000),
+    ("S7", 82000),  ("S8", 100_000), ("S9", 120_000),
+];
+
+const GRAND_MASTER_MIN_SCORE: i32 = 126_000;
+
+const GRAND_MASTER_CHECKPOINTS: &[(usize, Duration)] = &[
+    (300, Duration::from_secs(4 * 60 + 15)),
+    (500, Duration::from_secs(7 * 60 + 30)),
+    (MAX_LEVEL, Duration::from_secs(13 * 60 + 30)),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master_at_level(level: usize) -> Master {
+        Master {
+            level,
+            ..Master::new()
+        }
+    }
+
+    #[test]
+    fn grades_follow_the_score_thresholds() {
+        let mut master = Master::new();
+        for (score, grade) in [
+            (0, "9"),
+            (399, "9"),
+            (400, "8"),
+            (12_000, "1"),
+            (16_000, "S1"),
+            (125_999, "S9"),
+        ] {
+            master.score = score;
+            assert_eq!(master.grade(), grade, "score {score}");
+        }
+    }
+
+    #[test]
+    fn grand_master_needs_the_score_and_every_checkpoint_in_time() {
+        let mut master = Master::new();
+        master.score = GRAND_MASTER_MIN_SCORE;
+        master.play_time = Duration::from_secs(60);
+        master.raise_level_to(MAX_LEVEL);
+        assert_eq!(master.grade(), "GM");
+
+        let mut slow_master = Master::new();
+        slow_master.score = GRAND_MASTER_MIN_SCORE;
+        slow_master.play_time = Duration::from_secs(60 * 60);
+        slow_master.raise_level_to(MAX_LEVEL);
+        assert_eq!(slow_master.grade(), "S9");
+    }
+
+    #[test]
+    fn pieces_stop_raising_the_level_at_each_section_end() {
+        for (level, stop_level) in [(98, 99), (198, 199), (997, 998)] {
+            let mut master = master_at_level(level);
+            master.on_lock(None);
+            assert_eq!(master.level, stop_level);
+            master.on_lock(None);
+            assert_eq!(master.level, stop_level);
+            master.on_lines_cleared(1);
+            assert_eq!(master.level, stop_level + 1);
+        }
+    }
+
+    #[test]
+    fn line_clears_raise_the_level_by_the_rows_cleared_up_to_the_last_level() {
+        let mut master = master_at_level(10);
+        master.on_lines_cleared(4);
+        assert_eq!(master.level, 14);
+        assert_eq!(master.total_rows_cleared, 4);
+
+        let mut master = master_at_level(997);
+        master.on_lines_cleared(4);
+        assert_eq!(master.level, MAX_LEVEL);
+        assert!(master.is_finished());
+        assert_eq!(master.result(), GameResult::Completed);
+    }
+
+    #[test]
+    fn gravity_ramps_up_to_20g_with_a_reset_at_level_200() {
+        for (level, units) in [
+            (0, 4),
+            (29, 4),
+            (30, 6),
+            (199, 144),
+            (200, 4),
+            (251, 256),
+            (499, 768),
+            (500, 5120),
+            (MAX_LEVEL, 5120),
+        ] {
+            assert_eq!(gravity_units_per_frame(level), units, "level {level}");
+        }
+        assert_eq!(master_at_level(0).auto_drop_interval(), FRAME * 64);
+        assert_eq!(master_at_level(500).auto_drop_interval(), FRAME / 20);
+    }
+}
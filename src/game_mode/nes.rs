@@ -0,0 +1,115 @@
+use super::{GameMode, GameResult};
+use crate::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use crate::ruleset::{nes_frames, Ruleset};
+use std::time::Duration;
+
+pub const MAX_START_LEVEL: usize = 19;
+const NUM_LINES_PER_LEVEL: usize = 10;
+
+// Classic NES rules: gravity from the NES frames-per-row table, NES scoring,
+// and the NES level progression from the selected start level.
+pub struct Nes {
+    start_level: usize,
+    total_rows_cleared: usize,
+    opt_top_out: Option<TopOut>,
+}
+
+impl Nes {
+    pub fn new(start_level: usize) -> Self {
+        Nes {
+            start_level: start_level.min(MAX_START_LEVEL),
+            total_rows_cleared: 0,
+            opt_top_out: None,
+        }
+    }
+
+    // The first level-up takes longer the higher the start level;
+    // after that the level goes up every 10 lines.
+    fn level(&self) -> usize {
+        let first_level_up = (self.start_level * NUM_LINES_PER_LEVEL + NUM_LINES_PER_LEVEL).min(
+            (self.start_level * NUM_LINES_PER_LEVEL)
+                .saturating_sub(50)
+                .max(100),
+        );
+        if self.total_rows_cleared < first_level_up {
+            self.start_level
+        } else {
+            self.start_level + 1 + (self.total_rows_cleared - first_level_up) / NUM_LINES_PER_LEVEL
+        }
+    }
+}
+
+impl GameMode for Nes {
+    fn name(&self) -> &'static str {
+        "NES"
+    }
+
+    fn ruleset(&self) -> Ruleset {
+        Ruleset::nes()
+    }
+
+    fn on_tick(&mut self, board: &mut Board, _elapsed: Duration) {
+        board.set_level(self.level());
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.opt_top_out = self.opt_top_out.or(opt_top_out);
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.total_rows_cleared += num_lines_cleared;
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        nes_frames(frames_per_row(self.level()))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.opt_top_out.is_some()
+    }
+
+    fn result(&self) -> GameResult {
+        if let Some(top_out) = self.opt_top_out {
+            GameResult::ToppedOut(top_out)
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    fn opt_level(&self) -> Option<usize> {
+        Some(self.level())
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Start level: {}", self.start_level),
+            format!("Level: {}", self.level()),
+            format!("Lines: {}", self.total_rows_cleared),
+        ]
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "nes_high_score.txt"
+    }
+}
+
+#[rustfmt::skip]
+fn frames_per_row(level: usize) -> u32 {
+    match level {
+        0       => 48,
+        1       => 43,
+        2       => 38,
+        3       => 33,
+        4       => 28,
+        5       => 23,
+        6       => 18,
+        7       => 13,
+        8       => 8,
+        9       => 6,
+        10..=12 => 5,
+        13..=15 => 4,
+        16..=18 => 3,
+        19..=28 => 2,
+        _       => 1,
+    }
+}
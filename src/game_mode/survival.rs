@@ -0,0 +1,97 @@
+use super::classic::Classic;
+use super::garbage::GarbageGenerator;
+use super::{format_duration, GameMode, GameResult};
+use crate::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use std::time::Duration;
+
+pub const DEFAULT_INITIAL_GARBAGE_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_GARBAGE_INTERVAL: Duration = Duration::from_secs(2);
+const GARBAGE_INTERVAL_SCALE_FACTOR: f64 = 0.9;
+const MESSINESS: f32 = 0.25;
+
+// Survive for as long as possible while a garbage row rises from the bottom
+// every so often, at an ever shorter interval.
+// Gravity and top-out follow the classic rules.
+pub struct Survival {
+    classic: Classic,
+    garbage_generator: GarbageGenerator,
+    garbage_interval: Duration,
+    next_garbage_time: Duration,
+    num_garbage_rows_pushed: usize,
+    play_time: Duration,
+}
+
+impl Survival {
+    pub fn new(initial_garbage_interval: Duration) -> Self {
+        Survival {
+            classic: Classic::new(),
+            garbage_generator: GarbageGenerator::new(MESSINESS),
+            garbage_interval: initial_garbage_interval,
+            next_garbage_time: initial_garbage_interval,
+            num_garbage_rows_pushed: 0,
+            play_time: Duration::ZERO,
+        }
+    }
+}
+
+impl GameMode for Survival {
+    fn name(&self) -> &'static str {
+        "Survival"
+    }
+
+    fn on_tick(&mut self, board: &mut Board, elapsed: Duration) {
+        self.play_time = elapsed;
+        if elapsed < self.next_garbage_time {
+            return;
+        }
+
+        let hole_cols = self.garbage_generator.next_hole_cols(1, board.num_cols());
+        let opt_top_out = board.insert_garbage_rows(&hole_cols);
+        self.classic.on_lock(opt_top_out);
+        self.num_garbage_rows_pushed += 1;
+
+        self.garbage_interval = self
+            .garbage_interval
+            .mul_f64(GARBAGE_INTERVAL_SCALE_FACTOR)
+            .max(MIN_GARBAGE_INTERVAL);
+        self.next_garbage_time = elapsed + self.garbage_interval;
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.classic.on_lock(opt_top_out);
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.classic.on_lines_cleared(num_lines_cleared);
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        self.classic.auto_drop_interval()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.classic.is_finished()
+    }
+
+    fn result(&self) -> GameResult {
+        self.classic.result()
+    }
+
+    fn status_lines(&self) -> Vec<String> {
+        vec![format!(
+            "Next row {}",
+            format_duration(self.next_garbage_time.saturating_sub(self.play_time))
+        )]
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Survived: {}", format_duration(self.play_time)),
+            format!("Garbage rows: {}", self.num_garbage_rows_pushed),
+        ]
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "survival_high_score.txt"
+    }
+}
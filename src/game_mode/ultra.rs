@@ -0,0 +1,78 @@
+use super::classic::Classic;
+use super::{format_duration, GameMode, GameResult};
+use crate::board::{Board, NumRowsClearedThisUpdate, TopOut};
+use std::time::Duration;
+
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(120);
+
+// Score as much as possible before the clock runs out.
+// Gravity and top-out follow the classic rules.
+pub struct Ultra {
+    classic: Classic,
+    time_limit: Duration,
+    time_remaining: Duration,
+}
+
+impl Ultra {
+    pub fn new(time_limit: Duration) -> Self {
+        Ultra {
+            classic: Classic::new(),
+            time_limit,
+            time_remaining: time_limit,
+        }
+    }
+
+    fn time_up(&self) -> bool {
+        self.time_remaining.is_zero()
+    }
+}
+
+impl GameMode for Ultra {
+    fn name(&self) -> &'static str {
+        "Ultra"
+    }
+
+    fn on_tick(&mut self, _board: &mut Board, elapsed: Duration) {
+        self.time_remaining = self.time_limit.saturating_sub(elapsed);
+    }
+
+    fn on_lock(&mut self, opt_top_out: Option<TopOut>) {
+        self.classic.on_lock(opt_top_out);
+    }
+
+    fn on_lines_cleared(&mut self, num_lines_cleared: NumRowsClearedThisUpdate) {
+        self.classic.on_lines_cleared(num_lines_cleared);
+    }
+
+    fn auto_drop_interval(&self) -> Duration {
+        self.classic.auto_drop_interval()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.time_up() || self.classic.is_finished()
+    }
+
+    fn result(&self) -> GameResult {
+        // Playing until the clock runs out completes the run.
+        if self.time_up() {
+            GameResult::Completed
+        } else {
+            self.classic.result()
+        }
+    }
+
+    fn status_lines(&self) -> Vec<String> {
+        vec![format!(
+            "Time left {}",
+            format_duration(self.time_remaining)
+        )]
+    }
+
+    fn leaderboard_filename(&self) -> Option<&'static str> {
+        Some("ultra_leaderboard.txt")
+    }
+
+    fn high_score_filename(&self) -> &'static str {
+        "ultra_high_score.txt"
+    }
+}
@@ -0,0 +1,77 @@
+use super::board::Board;
+use super::game_mode::format_duration;
+use std::time::Duration;
+
+// What the HUD shows next to the board during play.
+#[derive(Clone, Debug)]
+pub struct Hud {
+    pub score: i32,
+    pub high_score: i32,
+    pub opt_level: Option<usize>,
+    pub num_lines: usize,
+    pub elapsed: Duration,
+    pub num_pieces: u64,
+    pub opt_combo: Option<usize>,
+    pub back_to_back: bool,
+    // Mode-specific lines, shown below the rest.
+    pub status_lines: Vec<String>,
+}
+
+impl Hud {
+    // The board's statistics; the level and status lines come from the game mode.
+    pub fn new(board: &Board, elapsed: Duration) -> Self {
+        Hud {
+            score: board.score(),
+            // Kept up to date as the score rises; it is saved once the game is over.
+            high_score: board.high_score(),
+            opt_level: None,
+            num_lines: board.num_rows_cleared(),
+            elapsed,
+            num_pieces: board.num_pieces_locked(),
+            opt_combo: board.opt_combo(),
+            back_to_back: board.back_to_back(),
+            status_lines: Vec::new(),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn pieces_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.num_pieces as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    // Label and value pairs, from the top down.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![
+            ("Score", self.score.to_string()),
+            ("High score", self.high_score.to_string()),
+        ];
+        if let Some(level) = self.opt_level {
+            entries.push(("Level", level.to_string()));
+        }
+        entries.extend([
+            ("Lines", self.num_lines.to_string()),
+            ("Time", format_duration(self.elapsed)),
+            ("Pieces", self.num_pieces.to_string()),
+            ("PPS", format!("{:.2}", self.pieces_per_second())),
+        ]);
+        entries
+    }
+
+    // For example "Combo 2  B2B", while the player keeps up a streak. The first
+    // clear of a run doesn't count as a combo yet.
+    pub fn streak_text(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(combo) = self.opt_combo.filter(|&combo| combo > 0) {
+            parts.push(format!("Combo {combo}"));
+        }
+        if self.back_to_back {
+            parts.push(String::from("B2B"));
+        }
+        (!parts.is_empty()).then(|| parts.join("  "))
+    }
+}
@@ -0,0 +1,146 @@
+use super::ruleset::{KeyRepeat, Ruleset};
+use super::user_action::UserAction;
+use super::user_move::UserMove;
+use macroquad::prelude::{get_keys_down, get_keys_pressed, KeyCode};
+use std::time::Instant;
+
+// The keys a frontend read for one frame: those pressed since the last frame and
+// those held down.
+#[derive(Clone, Debug, Default)]
+pub struct KeyboardState {
+    pub pressed: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+}
+
+impl KeyboardState {
+    // Needs the macroquad window.
+    pub fn from_macroquad() -> Self {
+        KeyboardState {
+            pressed: get_keys_pressed().into_iter().collect(),
+            down: get_keys_down().into_iter().collect(),
+        }
+    }
+
+    pub fn is_pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    pub fn is_down(&self, key: KeyCode) -> bool {
+        self.down.contains(&key)
+    }
+}
+
+// Turns keyboard state into user actions, applying the ruleset's key repeat to
+// left/right.
+// Pressing down soft drops one row; holding it speeds up gravity instead
+// of repeating, see `soft_drop_held`.
+pub struct Input {
+    key_repeat: KeyRepeat,
+    opt_auto_shift: Option<AutoShift>,
+    opt_last_action_time: Option<Instant>,
+}
+
+struct AutoShift {
+    key: KeyCode,
+    next_shift_time: Instant,
+}
+
+impl Input {
+    pub fn new(ruleset: &Ruleset) -> Self {
+        Input {
+            key_repeat: ruleset.key_repeat,
+            opt_auto_shift: None,
+            opt_last_action_time: None,
+        }
+    }
+
+    pub fn get_user_action(&mut self, keys: &KeyboardState, now: Instant) -> Option<UserAction> {
+        if let (Some(debounce), Some(last_action_time)) =
+            (self.key_repeat.debounce(), self.opt_last_action_time)
+        {
+            if now.saturating_duration_since(last_action_time) < debounce {
+                return None;
+            }
+        }
+
+        let opt_action = keys
+            .pressed
+            .iter()
+            .find_map(|&key| non_autorepeat_key_to_action(key))
+            .or_else(|| self.get_auto_shift_action(keys, now));
+        if opt_action.is_some() {
+            self.opt_last_action_time = Some(now);
+        }
+        opt_action
+    }
+
+    pub fn soft_drop_held(&self, keys: &KeyboardState) -> bool {
+        keys.is_down(KeyCode::Down)
+    }
+
+    // The hold and rotate keys that are down, in the order they apply to a
+    // spawning piece: hold first, then rotate.
+    pub fn held_initial_moves(&self, keys: &KeyboardState) -> Vec<UserMove> {
+        let mut initial_moves = Vec::new();
+        if keys.is_down(KeyCode::C) {
+            initial_moves.push(UserMove::Hold);
+        }
+        if keys.is_down(KeyCode::Up) {
+            initial_moves.push(UserMove::RotateCW);
+        } else if keys.is_down(KeyCode::Slash) {
+            initial_moves.push(UserMove::RotateCCW);
+        } else if keys.is_down(KeyCode::A) {
+            initial_moves.push(UserMove::Rotate180);
+        }
+        initial_moves
+    }
+
+    fn get_auto_shift_action(&mut self, keys: &KeyboardState, now: Instant) -> Option<UserAction> {
+        // A newly pressed direction shifts at once and takes over from a held one.
+        for key in [KeyCode::Left, KeyCode::Right] {
+            if keys.is_pressed(key) {
+                self.opt_auto_shift = Some(AutoShift {
+                    key,
+                    next_shift_time: now + self.key_repeat.das(),
+                });
+                return shift_key_to_action(key);
+            }
+        }
+
+        let auto_shift = self.opt_auto_shift.as_mut()?;
+        if !keys.is_down(auto_shift.key) {
+            self.opt_auto_shift = None;
+            return None;
+        }
+        if now < auto_shift.next_shift_time {
+            return None;
+        }
+        auto_shift.next_shift_time = now + self.key_repeat.arr();
+        shift_key_to_action(auto_shift.key)
+    }
+}
+
+#[rustfmt::skip]
+fn shift_key_to_action(key: KeyCode) -> Option<UserAction> {
+    match key {
+        KeyCode::Left  => Some(UserAction::UM(UserMove::Left)),
+        KeyCode::Right => Some(UserAction::UM(UserMove::Right)),
+        _              => None,
+    }
+}
+
+#[rustfmt::skip]
+fn non_autorepeat_key_to_action(key: KeyCode) -> Option<UserAction> {
+    match key {
+        KeyCode::Down  => Some(UserAction::UM(UserMove::SoftDown)),
+        KeyCode::Space => Some(UserAction::UM(UserMove::HardDown)),
+        KeyCode::W     => Some(UserAction::UM(UserMove::SonicDrop)),
+        KeyCode::S     => Some(UserAction::UM(UserMove::FirmDrop)),
+        KeyCode::Up    => Some(UserAction::UM(UserMove::RotateCW)),
+        KeyCode::Slash => Some(UserAction::UM(UserMove::RotateCCW)),
+        KeyCode::A     => Some(UserAction::UM(UserMove::Rotate180)),
+        KeyCode::C     => Some(UserAction::UM(UserMove::Hold)),
+        KeyCode::Q     => Some(UserAction::Quit),
+        _              => None,
+    }
+}
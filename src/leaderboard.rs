@@ -0,0 +1,72 @@
+use std::fs::{metadata, File};
+use std::io::{self, BufRead, Write};
+
+const MAX_NUM_ENTRIES: usize = 10;
+
+pub struct Leaderboard {
+    filename: &'static str,
+    scores: Vec<i32>,
+}
+
+pub type Rank = usize;
+
+impl Leaderboard {
+    pub fn load(filename: &'static str) -> Self {
+        let mut scores = read_scores_from_file(filename);
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(MAX_NUM_ENTRIES);
+        Leaderboard { filename, scores }
+    }
+
+    // Returns the zero-based rank of the score if it made it onto the board.
+    pub fn submit(&mut self, score: i32) -> Option<Rank> {
+        let rank = self
+            .scores
+            .iter()
+            .position(|&s| score > s)
+            .unwrap_or(self.scores.len());
+        if rank >= MAX_NUM_ENTRIES {
+            return None;
+        }
+        self.scores.insert(rank, score);
+        self.scores.truncate(MAX_NUM_ENTRIES);
+        write_scores_to_file(self.filename, &self.scores);
+        Some(rank)
+    }
+
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+}
+
+fn read_scores_from_file(filename: &str) -> Vec<i32> {
+    if metadata(filename).is_ok() {
+        if let Ok(file) = File::open(filename) {
+            let reader = io::BufReader::new(file);
+            return reader
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| line.trim().parse::<i32>().ok())
+                .collect();
+        }
+    }
+
+    Vec::new()
+}
+
+fn write_scores_to_file(filename: &str, scores: &[i32]) {
+    let mut file = match File::create(filename) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Failed to create or open file: {e}");
+            return;
+        }
+    };
+
+    for score in scores {
+        if let Err(e) = writeln!(file, "{score}") {
+            eprintln!("Failed to write to file: {e}");
+            return;
+        }
+    }
+}
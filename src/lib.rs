@@ -0,0 +1,14 @@
+pub mod args;
+pub mod board;
+pub mod draw;
+pub mod engine;
+pub mod game_mode;
+mod hud;
+pub mod input;
+mod leaderboard;
+mod ruleset;
+mod tetromino_move;
+mod theme;
+pub mod tui;
+mod user_action;
+mod user_move;
@@ -0,0 +1,377 @@
+use super::board::randomizer::Randomizer;
+use super::board::rotation_system::{Rotation180, RotationSystem};
+use std::time::Duration;
+
+// One frame at 60 frames per second.
+pub const FRAME: Duration = Duration::from_nanos(16_666_667);
+
+// One frame of the NTSC NES, which runs at 60.0988 frames per second.
+pub const NES_FRAME: Duration = Duration::from_nanos(16_639_267);
+
+pub fn frames(num_frames: u32) -> Duration {
+    FRAME * num_frames
+}
+
+pub fn nes_frames(num_frames: u32) -> Duration {
+    NES_FRAME * num_frames
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scoring {
+    // 100/300/500/800 per clear, 1 per soft-dropped row and 2 per hard-dropped row.
+    Standard,
+    // 40/100/300/1200 per clear, multiplied by (level + 1), and 1 per soft-dropped row.
+    Nes,
+    // ceil((level + rows) / 4) * rows per clear, and 1 per soft-dropped row.
+    Tgm,
+}
+
+impl Scoring {
+    pub fn line_clear_points(self, num_rows_cleared: usize, level: usize) -> i32 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let level_multiplier = level as i32 + 1;
+        match self {
+            Scoring::Standard => match num_rows_cleared {
+                1 => 100,
+                2 => 300,
+                3 => 500,
+                4 => 800,
+                _ => 0,
+            },
+            Scoring::Nes => {
+                let base_points = match num_rows_cleared {
+                    1 => 40,
+                    2 => 100,
+                    3 => 300,
+                    4 => 1200,
+                    _ => 0,
+                };
+                base_points * level_multiplier
+            }
+            Scoring::Tgm => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let num_rows = num_rows_cleared as i32;
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let level = level as i32;
+                ((level + num_rows + 3) / 4) * num_rows
+            }
+        }
+    }
+
+    pub fn soft_drop_points(self) -> i32 {
+        1
+    }
+
+    pub fn hard_drop_points(self, num_rows_dropped: usize) -> i32 {
+        match self {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            Scoring::Standard => 2 * num_rows_dropped as i32,
+            Scoring::Nes | Scoring::Tgm => 0,
+        }
+    }
+}
+
+// How fast a piece falls while soft drop is held.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SoftDropSpeed {
+    // A multiple of the current gravity.
+    Factor(u32),
+    // A fixed interval per row whatever the gravity, as on the NES.
+    Fixed(Duration),
+    // Straight down to the ghost position.
+    Instant,
+}
+
+impl SoftDropSpeed {
+    // Accepts a gravity factor such as `20`, or `instant`.
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "instant" => Some(SoftDropSpeed::Instant),
+            _ => match arg.parse::<u32>() {
+                Ok(factor) if factor > 0 => Some(SoftDropSpeed::Factor(factor)),
+                _ => None,
+            },
+        }
+    }
+
+    // Soft drop never makes a piece fall slower than gravity alone.
+    pub fn interval(self, auto_drop_interval: Duration) -> Duration {
+        match self {
+            SoftDropSpeed::Factor(factor) => auto_drop_interval / factor.max(1),
+            SoftDropSpeed::Fixed(interval) => interval.min(auto_drop_interval),
+            SoftDropSpeed::Instant => Duration::ZERO,
+        }
+    }
+}
+
+// The pause after a piece locks before the next one becomes active.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EntryDelay {
+    pub base: Duration,
+    // Added for every 4 rows of height at which the piece locked.
+    pub per_four_rows_of_lock_height: Duration,
+    pub max: Duration,
+    // Added when the lock cleared any rows.
+    pub line_clear: Duration,
+}
+
+impl EntryDelay {
+    pub fn fixed(are: Duration, line_clear: Duration) -> Self {
+        EntryDelay {
+            base: are,
+            per_four_rows_of_lock_height: Duration::ZERO,
+            max: are,
+            line_clear,
+        }
+    }
+
+    pub const NONE: EntryDelay = EntryDelay {
+        base: Duration::ZERO,
+        per_four_rows_of_lock_height: Duration::ZERO,
+        max: Duration::ZERO,
+        line_clear: Duration::ZERO,
+    };
+
+    pub fn after_lock(&self, lock_height: usize, num_rows_cleared: usize) -> Duration {
+        #[allow(clippy::cast_possible_truncation)]
+        let num_height_steps = ((lock_height + 2) / 4) as u32;
+        let are = (self.base + self.per_four_rows_of_lock_height * num_height_steps).min(self.max);
+        if num_rows_cleared > 0 {
+            are + self.line_clear
+        } else {
+            are
+        }
+    }
+}
+
+// How held left/right keys repeat.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyRepeat {
+    // The original input handling: a held key repeats at this interval, and no key
+    // acts within it of the last one that did.
+    Debounce(Duration),
+    // Delayed auto shift (DAS): how long left/right must be held before auto-repeat
+    // starts, then the auto repeat rate (ARR): the interval between repeated shifts.
+    DasArr { das: Duration, arr: Duration },
+}
+
+impl KeyRepeat {
+    pub fn das(self) -> Duration {
+        match self {
+            KeyRepeat::Debounce(interval) => interval,
+            KeyRepeat::DasArr { das, .. } => das,
+        }
+    }
+
+    pub fn arr(self) -> Duration {
+        match self {
+            KeyRepeat::Debounce(interval) => interval,
+            KeyRepeat::DasArr { arr, .. } => arr,
+        }
+    }
+
+    pub fn debounce(self) -> Option<Duration> {
+        match self {
+            KeyRepeat::Debounce(interval) => Some(interval),
+            KeyRepeat::DasArr { .. } => None,
+        }
+    }
+}
+
+// Where new pieces appear.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnPosition {
+    // In the two rows just above the visible field, dropping one row at once
+    // if nothing is in the way.
+    Guideline,
+    // In the top rows of the visible field, as on the NES.
+    TopOfField,
+}
+
+// Which top out rules end the game. A push out by rising garbage always does.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TopOutRules {
+    pub block_out: bool,
+    pub lock_out: bool,
+    pub partial_lock_out: bool,
+}
+
+impl TopOutRules {
+    pub const GUIDELINE: TopOutRules = TopOutRules {
+        block_out: true,
+        lock_out: true,
+        partial_lock_out: false,
+    };
+
+    pub const BLOCK_OUT_ONLY: TopOutRules = TopOutRules {
+        block_out: true,
+        lock_out: false,
+        partial_lock_out: false,
+    };
+}
+
+// The mechanics a game mode is played with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ruleset {
+    pub rotation_system: RotationSystem,
+    pub rotation_180: Rotation180,
+    pub randomizer: Randomizer,
+    pub scoring: Scoring,
+    pub ghost_enabled: bool,
+    pub hard_drop_enabled: bool,
+    // Sonic and firm drops.
+    pub sonic_drop_enabled: bool,
+    pub hold_enabled: bool,
+    // Initial rotation and hold: rotate and hold keys that are down when the
+    // entry delay ends apply to the piece as it spawns.
+    pub irs_ihs_enabled: bool,
+    pub key_repeat: KeyRepeat,
+    pub soft_drop_speed: SoftDropSpeed,
+    // Whether soft dropping onto the stack locks the piece at once. Otherwise
+    // gravity and the lock delay decide when it locks.
+    pub soft_drop_locks_on_ground: bool,
+    pub entry_delay: EntryDelay,
+    // How long a grounded piece can still move before it locks. Without a lock
+    // delay the piece locks on the first gravity step that finds it grounded.
+    pub lock_delay: Option<Duration>,
+    pub top_out_rules: TopOutRules,
+    pub spawn_position: SpawnPosition,
+    // How many upcoming pieces the next queue shows, up to `MAX_NUM_NEXT_PIECES`.
+    pub num_next_pieces: usize,
+}
+
+impl Ruleset {
+    pub fn modern() -> Self {
+        Ruleset {
+            rotation_system: RotationSystem::NoKicks,
+            rotation_180: Rotation180::SrsPlus,
+            randomizer: Randomizer::Uniform,
+            scoring: Scoring::Standard,
+            ghost_enabled: true,
+            hard_drop_enabled: true,
+            sonic_drop_enabled: true,
+            hold_enabled: true,
+            irs_ihs_enabled: false,
+            key_repeat: KeyRepeat::Debounce(Duration::from_millis(50)),
+            soft_drop_speed: SoftDropSpeed::Factor(20),
+            soft_drop_locks_on_ground: false,
+            entry_delay: EntryDelay::NONE,
+            lock_delay: None,
+            top_out_rules: TopOutRules::GUIDELINE,
+            spawn_position: SpawnPosition::Guideline,
+            num_next_pieces: 5,
+        }
+    }
+
+    pub fn nes() -> Self {
+        Ruleset {
+            rotation_system: RotationSystem::Nrs,
+            rotation_180: Rotation180::Disabled,
+            randomizer: Randomizer::Nes,
+            scoring: Scoring::Nes,
+            ghost_enabled: false,
+            hard_drop_enabled: false,
+            sonic_drop_enabled: false,
+            hold_enabled: false,
+            irs_ihs_enabled: false,
+            key_repeat: KeyRepeat::DasArr {
+                das: nes_frames(16),
+                arr: nes_frames(6),
+            },
+            soft_drop_speed: SoftDropSpeed::Fixed(nes_frames(2)),
+            soft_drop_locks_on_ground: true,
+            entry_delay: EntryDelay {
+                base: nes_frames(10),
+                per_four_rows_of_lock_height: nes_frames(2),
+                max: nes_frames(18),
+                line_clear: nes_frames(18),
+            },
+            lock_delay: None,
+            top_out_rules: TopOutRules::BLOCK_OUT_ONLY,
+            spawn_position: SpawnPosition::TopOfField,
+            num_next_pieces: 1,
+        }
+    }
+}
+
+pub const MAX_NUM_NEXT_PIECES: usize = 7;
+
+pub fn parse_num_next_pieces(arg: &str) -> Option<usize> {
+    match arg.parse::<usize>() {
+        Ok(value) if value <= MAX_NUM_NEXT_PIECES => Some(value),
+        _ => None,
+    }
+}
+
+// Ruleset settings chosen on the command line, which take precedence over the mode's.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RulesetOverrides {
+    pub opt_rotation_system: Option<RotationSystem>,
+    pub opt_rotation_180: Option<Rotation180>,
+    pub opt_soft_drop_speed: Option<SoftDropSpeed>,
+    pub opt_soft_drop_locks_on_ground: Option<bool>,
+    pub opt_num_next_pieces: Option<usize>,
+    pub opt_irs_ihs_enabled: Option<bool>,
+}
+
+impl RulesetOverrides {
+    pub fn apply_to(self, ruleset: &mut Ruleset) {
+        if let Some(rotation_system) = self.opt_rotation_system {
+            ruleset.rotation_system = rotation_system;
+        }
+        if let Some(rotation_180) = self.opt_rotation_180 {
+            ruleset.rotation_180 = rotation_180;
+        }
+        if let Some(soft_drop_speed) = self.opt_soft_drop_speed {
+            ruleset.soft_drop_speed = soft_drop_speed;
+        }
+        if let Some(soft_drop_locks_on_ground) = self.opt_soft_drop_locks_on_ground {
+            ruleset.soft_drop_locks_on_ground = soft_drop_locks_on_ground;
+        }
+        if let Some(num_next_pieces) = self.opt_num_next_pieces {
+            ruleset.num_next_pieces = num_next_pieces;
+        }
+        if let Some(irs_ihs_enabled) = self.opt_irs_ihs_enabled {
+            ruleset.irs_ihs_enabled = irs_ihs_enabled;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_clear_points_follow_each_scoring() {
+        for (num_rows_cleared, standard, nes_at_level_9) in [
+            (0, 0, 0),
+            (1, 100, 400),
+            (2, 300, 1000),
+            (3, 500, 3000),
+            (4, 800, 12000),
+        ] {
+            assert_eq!(
+                Scoring::Standard.line_clear_points(num_rows_cleared, 9),
+                standard
+            );
+            assert_eq!(
+                Scoring::Nes.line_clear_points(num_rows_cleared, 9),
+                nes_at_level_9
+            );
+        }
+        assert_eq!(Scoring::Nes.line_clear_points(4, 0), 1200);
+        // ceil((level + rows) / 4) * rows
+        assert_eq!(Scoring::Tgm.line_clear_points(1, 0), 1);
+        assert_eq!(Scoring::Tgm.line_clear_points(4, 0), 4);
+        assert_eq!(Scoring::Tgm.line_clear_points(2, 5), 4);
+        assert_eq!(Scoring::Tgm.line_clear_points(4, 100), 104);
+    }
+
+    #[test]
+    fn only_standard_scoring_rewards_hard_drops() {
+        assert_eq!(Scoring::Standard.hard_drop_points(0), 0);
+        assert_eq!(Scoring::Standard.hard_drop_points(18), 36);
+        assert_eq!(Scoring::Nes.hard_drop_points(18), 0);
+        assert_eq!(Scoring::Tgm.hard_drop_points(18), 0);
+    }
+}
@@ -10,7 +10,13 @@ impl TetrominoMove {
     pub fn resets_down_timer(self) -> bool {
         matches!(
             self,
-            TetrominoMove::UM(UserMove::SoftDown | UserMove::HardDown)
+            TetrominoMove::UM(
+                UserMove::SoftDown
+                    | UserMove::HardDown
+                    | UserMove::SonicDrop
+                    | UserMove::FirmDrop
+                    | UserMove::Hold
+            )
         )
     }
 }
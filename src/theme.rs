@@ -0,0 +1,529 @@
+use super::board::cursor::piece::Shape;
+use macroquad::color::Color;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CellColors {
+    pub fill: Color,
+    pub outline: Color,
+}
+
+impl CellColors {
+    const fn new(fill: u32, outline: u32) -> Self {
+        CellColors {
+            fill: Color::from_hex(fill),
+            outline: Color::from_hex(outline),
+        }
+    }
+
+    // A fill with a darker outline of the same hue.
+    fn shaded(fill: Color) -> Self {
+        CellColors {
+            fill,
+            outline: Color::new(fill.r * 0.7, fill.g * 0.7, fill.b * 0.7, fill.a),
+        }
+    }
+}
+
+// An image laid over every block and tinted with the block's fill color, so that
+// one grey skin serves every shape.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    // RGBA bytes, row by row from the top left. Shared, as themes are cloned.
+    pub pixels: Arc<[u8]>,
+}
+
+impl Texture {
+    // Reads a PNG image of any color type.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+        let bytes = &buf[..info.buffer_size()];
+        let pixels: Vec<u8> = match info.color_type {
+            png::ColorType::Rgba => bytes.to_vec(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => bytes.iter().flat_map(|&g| [g, g, g, 0xff]).collect(),
+            png::ColorType::Indexed => return Err(String::from("unsupported indexed image")),
+        };
+        Ok(Texture {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels: pixels.into(),
+        })
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let i = (y * self.width + x) * 4;
+        Color::from_rgba(
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        )
+    }
+}
+
+// The colors the board is drawn with. Pieces are colored by shape, both in play
+// and once locked into the stack.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    // In the order of `SHAPE_KEYS`.
+    shape_colors: [CellColors; 7],
+    // When set, the piece in play is drawn in these colors instead of its shape's.
+    opt_active: Option<CellColors>,
+    pub garbage: CellColors,
+    pub empty: CellColors,
+    pub being_removed: CellColors,
+    pub ghost: Color,
+    pub background: Color,
+    pub text: Color,
+    // The message of the victory screen.
+    pub accent: Color,
+    // Marks each piece with a pattern of its own, so that shapes can be told
+    // apart without relying on color.
+    pub patterns: bool,
+    // How strongly the ghost piece is filled in, from 0 (outline only) to 1.
+    pub ghost_opacity: f32,
+    // Drawn over the fill of pieces and garbage in the window and in images.
+    pub opt_texture: Option<Texture>,
+}
+
+const SHAPE_KEYS: [&str; 7] = ["I", "O", "T", "S", "Z", "J", "L"];
+
+impl Theme {
+    pub fn guideline() -> Self {
+        Theme {
+            shape_colors: [
+                CellColors::new(0x00_f0_f0, 0x00_a8_a8),
+                CellColors::new(0xf0_f0_00, 0xa8_a8_00),
+                CellColors::new(0xa0_00_f0, 0x70_00_a8),
+                CellColors::new(0x00_f0_00, 0x00_a8_00),
+                CellColors::new(0xf0_00_00, 0xa8_00_00),
+                CellColors::new(0x00_00_f0, 0x00_00_a8),
+                CellColors::new(0xf0_a0_00, 0xa8_70_00),
+            ],
+            opt_active: None,
+            garbage: CellColors::new(0x80_80_80, 0x50_50_50),
+            empty: CellColors::new(0xff_ff_ff, 0xfc_fc_fc),
+            being_removed: CellColors::new(0xf0_f0_f0, 0xc0_c0_c0),
+            ghost: Color::from_hex(0x90_90_90),
+            background: Color::from_hex(0xc8_c8_c8),
+            text: Color::from_hex(0x50_50_50),
+            accent: Color::from_hex(0x00_75_2c),
+            patterns: false,
+            ghost_opacity: 0.0,
+            opt_texture: None,
+        }
+    }
+
+    // The original look: every piece brown in play and grey once locked.
+    pub fn monochrome() -> Self {
+        Theme {
+            shape_colors: [CellColors::new(0x50_50_50, 0x82_82_82); 7],
+            opt_active: Some(CellColors::new(0x7f_6a_4f, 0xd3_b0_83)),
+            garbage: CellColors::new(0x82_82_82, 0x50_50_50),
+            empty: CellColors::new(0xff_ff_ff, 0xfc_fc_fc),
+            being_removed: CellColors::new(0x00_e4_30, 0x00_9e_2f),
+            ghost: Color::from_hex(0xd3_b0_83),
+            background: Color::from_hex(0xc8_c8_c8),
+            text: Color::from_hex(0x50_50_50),
+            accent: Color::from_hex(0x00_9e_2f),
+            patterns: false,
+            ghost_opacity: 0.0,
+            opt_texture: None,
+        }
+    }
+
+    // Two shades of blue and white-centred pieces on black, after level 0 on the NES.
+    pub fn nes() -> Self {
+        let white_centred = CellColors::new(0xfc_fc_fc, 0x00_58_f8);
+        let primary = CellColors::new(0x00_58_f8, 0x00_3c_b0);
+        let secondary = CellColors::new(0x3c_bc_fc, 0x20_88_c0);
+        Theme {
+            shape_colors: [
+                white_centred,
+                white_centred,
+                white_centred,
+                primary,
+                secondary,
+                primary,
+                secondary,
+            ],
+            opt_active: None,
+            garbage: CellColors::new(0x7c_7c_7c, 0x50_50_50),
+            empty: CellColors::new(0x00_00_00, 0x10_10_10),
+            being_removed: CellColors::new(0xfc_fc_fc, 0xbc_bc_bc),
+            ghost: Color::from_hex(0x7c_7c_7c),
+            background: Color::from_hex(0x00_00_00),
+            text: Color::from_hex(0xfc_fc_fc),
+            accent: Color::from_hex(0x3c_bc_fc),
+            patterns: false,
+            ghost_opacity: 0.0,
+            opt_texture: None,
+        }
+    }
+
+    // The Okabe-Ito palette, which stays distinguishable with the common forms of
+    // color blindness, with patterns on.
+    pub fn colorblind() -> Self {
+        Theme {
+            shape_colors: [
+                CellColors::new(0x56_b4_e9, 0x2a_6e_94),
+                CellColors::new(0xf0_e4_42, 0x96_8c_1a),
+                CellColors::new(0xcc_79_a7, 0x7f_3c_66),
+                CellColors::new(0x00_9e_73, 0x00_5a_41),
+                CellColors::new(0xd5_5e_00, 0x80_38_00),
+                CellColors::new(0x00_72_b2, 0x00_40_66),
+                CellColors::new(0xe6_9f_00, 0x8c_60_00),
+            ],
+            opt_active: None,
+            garbage: CellColors::new(0x80_80_80, 0x40_40_40),
+            empty: CellColors::new(0xff_ff_ff, 0xf0_f0_f0),
+            being_removed: CellColors::new(0x00_00_00, 0x00_00_00),
+            ghost: Color::from_hex(0x00_00_00),
+            background: Color::from_hex(0xc8_c8_c8),
+            text: Color::from_hex(0x00_00_00),
+            accent: Color::from_hex(0x00_9e_73),
+            patterns: true,
+            ghost_opacity: 0.25,
+            opt_texture: None,
+        }
+    }
+
+    // Saturated pieces with white outlines on black.
+    pub fn high_contrast() -> Self {
+        let white = 0xff_ff_ff;
+        Theme {
+            shape_colors: [
+                CellColors::new(0x00_ff_ff, white),
+                CellColors::new(0xff_ff_00, white),
+                CellColors::new(0xff_00_ff, white),
+                CellColors::new(0x00_ff_00, white),
+                CellColors::new(0xff_00_00, white),
+                CellColors::new(0x40_80_ff, white),
+                CellColors::new(0xff_80_00, white),
+            ],
+            opt_active: None,
+            garbage: CellColors::new(0xc0_c0_c0, white),
+            empty: CellColors::new(0x00_00_00, 0x40_40_40),
+            being_removed: CellColors::new(white, white),
+            ghost: Color::from_hex(white),
+            background: Color::from_hex(0x00_00_00),
+            text: Color::from_hex(white),
+            accent: Color::from_hex(0x00_ff_00),
+            patterns: true,
+            ghost_opacity: 0.0,
+            opt_texture: None,
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "guideline" => Some(Theme::guideline()),
+            "monochrome" => Some(Theme::monochrome()),
+            "nes" => Some(Theme::nes()),
+            "colorblind" => Some(Theme::colorblind()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    // Accepts the name of a built-in theme or the path of a theme file.
+    pub fn from_arg(arg: &str) -> Self {
+        if let Some(theme) = Theme::built_in(arg) {
+            return theme;
+        }
+        Theme::load(arg).unwrap_or_else(|e| {
+            eprintln!("Failed to load theme '{arg}': {e}, using the default");
+            Theme::default()
+        })
+    }
+
+    // A theme file has one `key = value` setting per line, where the value is a
+    // fill color and optionally an outline color, as `#rrggbb`. Keys are the
+    // shapes `I O T S Z J L`, and `active`, `garbage`, `empty`, `being_removed`,
+    // `ghost`, `background`, `text` and `accent`. `patterns = on|off` and
+    // `ghost_opacity = X` (0 to 1) are also accepted, and `texture = PATH` names a
+    // PNG image to lay over every block, relative to the theme file. `base = NAME`
+    // starts from a built-in theme rather than the default one. Blank lines and
+    // lines starting with `#` are ignored.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        let mut theme = Theme::default();
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            theme
+                .apply_setting(line, dir)
+                .map_err(|e| format!("line {}: {e}", line_idx + 1))?;
+        }
+        Ok(theme)
+    }
+
+    fn apply_setting(&mut self, line: &str, dir: &Path) -> Result<(), String> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| String::from("expected 'key = value'"))?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "base" => {
+                *self = Theme::built_in(value).ok_or_else(|| format!("unknown theme '{value}'"))?;
+                return Ok(());
+            }
+            "patterns" => {
+                self.patterns = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("invalid value '{value}', expected on or off")),
+                };
+                return Ok(());
+            }
+            "ghost_opacity" => {
+                self.ghost_opacity = parse_opacity(value)
+                    .ok_or_else(|| format!("invalid opacity '{value}', expected 0 to 1"))?;
+                return Ok(());
+            }
+            "texture" => {
+                let texture_path = dir.join(value);
+                let texture = Texture::load(&texture_path)
+                    .map_err(|e| format!("failed to load '{}': {e}", texture_path.display()))?;
+                self.opt_texture = Some(texture);
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        let colors = parse_cell_colors(value)?;
+        if let Some(shape_idx) = SHAPE_KEYS.iter().position(|&shape_key| shape_key == key) {
+            self.shape_colors[shape_idx] = colors;
+            return Ok(());
+        }
+        match key {
+            "active" => self.opt_active = Some(colors),
+            "garbage" => self.garbage = colors,
+            "empty" => self.empty = colors,
+            "being_removed" => self.being_removed = colors,
+            "ghost" => self.ghost = colors.fill,
+            "background" => self.background = colors.fill,
+            "text" => self.text = colors.fill,
+            "accent" => self.accent = colors.fill,
+            _ => return Err(format!("unknown key '{key}'")),
+        }
+        Ok(())
+    }
+
+    pub fn shape(&self, shape: Shape) -> CellColors {
+        let shape_idx = match shape {
+            Shape::I => 0,
+            Shape::O => 1,
+            Shape::T => 2,
+            Shape::S => 3,
+            Shape::Z => 4,
+            Shape::J => 5,
+            Shape::L => 6,
+        };
+        self.shape_colors[shape_idx]
+    }
+
+    pub fn active(&self, shape: Shape) -> CellColors {
+        self.opt_active.unwrap_or_else(|| self.shape(shape))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::guideline()
+    }
+}
+
+fn parse_cell_colors(value: &str) -> Result<CellColors, String> {
+    let mut colors = value.split_whitespace().map(parse_color);
+    let fill = colors
+        .next()
+        .ok_or_else(|| String::from("missing color"))??;
+    match colors.next() {
+        None => Ok(CellColors::shaded(fill)),
+        Some(outline) => Ok(CellColors {
+            fill,
+            outline: outline?,
+        }),
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    value
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(Color::from_hex)
+        .ok_or_else(|| format!("invalid color '{value}', expected #rrggbb"))
+}
+
+pub fn parse_opacity(arg: &str) -> Option<f32> {
+    match arg.parse::<f32>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Some(value),
+        _ => None,
+    }
+}
+
+pub const MIN_FONT_SCALE: f32 = 0.5;
+pub const MAX_FONT_SCALE: f32 = 3.0;
+
+pub fn parse_font_scale(arg: &str) -> Option<f32> {
+    match arg.parse::<f32>() {
+        Ok(value) if (MIN_FONT_SCALE..=MAX_FONT_SCALE).contains(&value) => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // A directory of its own for each test, as tests run in parallel.
+    fn temp_dir(test_name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("quads-theme-{test_name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_theme(dir: &Path, contents: &str) -> String {
+        let path = dir.join("test.theme");
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn parses_a_fill_and_outline() {
+        let colors = parse_cell_colors("#ff0000  #00ff00").unwrap();
+        assert_eq!(colors.fill, Color::from_hex(0xff_00_00));
+        assert_eq!(colors.outline, Color::from_hex(0x00_ff_00));
+    }
+
+    #[test]
+    fn shades_the_outline_when_only_a_fill_is_given() {
+        let colors = parse_cell_colors("#c80070").unwrap();
+        assert_eq!(colors, CellColors::shaded(Color::from_hex(0xc8_00_70)));
+    }
+
+    #[test]
+    fn rejects_malformed_colors() {
+        assert!(parse_cell_colors("").is_err());
+        assert!(parse_cell_colors("ff0000").is_err());
+        assert!(parse_cell_colors("#ff00").is_err());
+        assert!(parse_cell_colors("#gg0000").is_err());
+        assert!(parse_cell_colors("#ff0000 red").is_err());
+    }
+
+    #[test]
+    fn applies_settings() {
+        let mut theme = Theme::default();
+        let dir = Path::new("");
+        theme.apply_setting("T = #123456 #654321", dir).unwrap();
+        theme.apply_setting("active=#010203", dir).unwrap();
+        theme.apply_setting("background = #101020", dir).unwrap();
+        theme.apply_setting("accent = #00c000", dir).unwrap();
+        theme.apply_setting("patterns = on", dir).unwrap();
+        theme.apply_setting("ghost_opacity = 0.5", dir).unwrap();
+        assert_eq!(
+            theme.shape(Shape::T),
+            CellColors::new(0x12_34_56, 0x65_43_21)
+        );
+        assert_eq!(theme.active(Shape::I).fill, Color::from_hex(0x01_02_03));
+        assert_eq!(theme.background, Color::from_hex(0x10_10_20));
+        assert_eq!(theme.accent, Color::from_hex(0x00_c0_00));
+        assert!(theme.patterns);
+        assert!((theme.ghost_opacity - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn base_replaces_everything_set_before_it() {
+        let mut theme = Theme::default();
+        theme.apply_setting("T = #123456", Path::new("")).unwrap();
+        theme.apply_setting("base = nes", Path::new("")).unwrap();
+        assert_eq!(theme, Theme::nes());
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        let mut theme = Theme::default();
+        let dir = Path::new("");
+        assert!(theme.apply_setting("T #123456", dir).is_err());
+        assert!(theme.apply_setting("X = #123456", dir).is_err());
+        assert!(theme.apply_setting("base = sepia", dir).is_err());
+        assert!(theme.apply_setting("patterns = yes", dir).is_err());
+        assert!(theme.apply_setting("ghost_opacity = 2", dir).is_err());
+        assert!(theme.apply_setting("texture = missing.png", dir).is_err());
+        assert_eq!(theme, Theme::default());
+    }
+
+    #[test]
+    fn loads_a_theme_file() {
+        let dir = temp_dir("load");
+        let path = write_theme(
+            &dir,
+            "# A dark theme\n\nbase = nes\n  garbage = #606060 #303030  \nZ = #c00000\n",
+        );
+        let theme = Theme::load(&path).unwrap();
+        let mut expected = Theme::nes();
+        expected.garbage = CellColors::new(0x60_60_60, 0x30_30_30);
+        expected.shape_colors[4] = CellColors::shaded(Color::from_hex(0xc0_00_00));
+        assert_eq!(theme, expected);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reports_the_line_of_an_error() {
+        let dir = temp_dir("error");
+        let path = write_theme(&dir, "base = nes\n# fine so far\nL = orange\n");
+        let error = Theme::load(&path).unwrap_err();
+        assert!(error.starts_with("line 3:"), "{error}");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn loads_a_texture_next_to_the_theme_file() {
+        let dir = temp_dir("texture");
+        let file = File::create(dir.join("tile.png")).unwrap();
+        let mut encoder = png::Encoder::new(file, 2, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0xff, 0x80]).unwrap();
+        writer.finish().unwrap();
+        let path = write_theme(&dir, "texture = tile.png\n");
+
+        let texture = Theme::load(&path).unwrap().opt_texture.unwrap();
+        assert_eq!((texture.width, texture.height), (2, 1));
+        assert_eq!(
+            texture.pixel(0, 0),
+            Color::from_rgba(0xff, 0xff, 0xff, 0xff)
+        );
+        assert_eq!(
+            texture.pixel(1, 0),
+            Color::from_rgba(0x80, 0x80, 0x80, 0xff)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
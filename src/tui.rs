@@ -0,0 +1,363 @@
+use super::board::cursor::piece::{Piece, Shape};
+use super::board::view::ViewCell;
+use super::board::Board;
+use super::hud::Hud;
+use super::input::KeyboardState;
+use super::theme::{CellColors, Theme};
+use crossterm::event::{
+    self, Event, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{self, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use macroquad::color::Color;
+use macroquad::input::KeyCode;
+use std::io::{self, Stdout, Write};
+use std::time::Instant;
+
+// Every cell is two characters wide, so that it comes out roughly square.
+const EMPTY_CELL: &str = " ·";
+const BLOCK_CELL: &str = "██";
+const GHOST_CELL: &str = "░░";
+const PREVIEW_WIDTH: usize = 4;
+// HUD labels are left-aligned and values right-aligned in these widths.
+const HUD_LABEL_WIDTH: usize = 11;
+const HUD_VALUE_WIDTH: usize = 9;
+
+// Puts the terminal in raw mode on the alternate screen, and restores it when
+// dropped, also on panic.
+pub struct Terminal {
+    out: Stdout,
+    reports_key_releases: bool,
+}
+
+impl Terminal {
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::stdout();
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        let reports_key_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if reports_key_releases {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+        Ok(Terminal {
+            out,
+            reports_key_releases,
+        })
+    }
+
+    pub fn reports_key_releases(&self) -> bool {
+        self.reports_key_releases
+    }
+
+    pub fn draw_board(&mut self, board: &Board, hud: &Hud, theme: &Theme) -> io::Result<()> {
+        let board_lines = board_lines(board, theme);
+        let side_panel_lines = side_panel_lines(board, hud, theme);
+        let num_lines = board_lines.len().max(side_panel_lines.len());
+        let lines: Vec<String> = (0..num_lines)
+            .map(|i| {
+                let board_line = board_lines
+                    .get(i)
+                    .map_or_else(|| " ".repeat(2 * board.num_cols() + 2), Clone::clone);
+                let side_panel_line = side_panel_lines.get(i).map_or("", String::as_str);
+                format!("{board_line}  {side_panel_line}")
+            })
+            .collect();
+        self.draw_lines(&lines)
+    }
+
+    // Draws from the top left corner, clearing whatever was left of the last frame.
+    pub fn draw_lines(&mut self, lines: &[String]) -> io::Result<()> {
+        for (row_idx, line) in lines.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            queue!(
+                self.out,
+                cursor::MoveTo(0, row_idx as u16),
+                Print(line),
+                ResetColor,
+                terminal::Clear(terminal::ClearType::UntilNewLine)
+            )?;
+        }
+        queue!(
+            self.out,
+            terminal::Clear(terminal::ClearType::FromCursorDown)
+        )?;
+        self.out.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.reports_key_releases {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Builds the keyboard state of each frame from terminal key events. When the
+// terminal reports key releases, keys are held from press to release. Otherwise
+// every press and key repeat counts as a fresh press and no key is ever held, so
+// moves follow the terminal's own key repeat.
+pub struct TerminalKeyboard {
+    reports_key_releases: bool,
+    down: Vec<KeyCode>,
+}
+
+impl TerminalKeyboard {
+    pub fn new(reports_key_releases: bool) -> Self {
+        TerminalKeyboard {
+            reports_key_releases,
+            down: Vec::new(),
+        }
+    }
+
+    // Reads the events that arrive until the deadline.
+    pub fn read_until(&mut self, deadline: Instant) -> io::Result<KeyboardState> {
+        let mut pressed = Vec::new();
+        while event::poll(deadline.saturating_duration_since(Instant::now()))? {
+            let Event::Key(key_event) = event::read()? else {
+                continue;
+            };
+            let Some(key) = key_code(key_event) else {
+                continue;
+            };
+            match key_event.kind {
+                KeyEventKind::Press => {
+                    pressed.push(key);
+                    if self.reports_key_releases && !self.down.contains(&key) {
+                        self.down.push(key);
+                    }
+                }
+                KeyEventKind::Repeat if !self.reports_key_releases => pressed.push(key),
+                KeyEventKind::Repeat => (),
+                KeyEventKind::Release => self.down.retain(|&down_key| down_key != key),
+            }
+        }
+        Ok(KeyboardState {
+            pressed,
+            down: self.down.clone(),
+        })
+    }
+}
+
+// The keys of the window frontend. Ctrl-C quits too, as raw mode keeps it from
+// interrupting the program.
+#[rustfmt::skip]
+fn key_code(key_event: KeyEvent) -> Option<KeyCode> {
+    use crossterm::event::KeyCode as TermKeyCode;
+    if key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key_event.code {
+            TermKeyCode::Char('c') => Some(KeyCode::Q),
+            _                      => None,
+        };
+    }
+    match key_event.code {
+        TermKeyCode::Left      => Some(KeyCode::Left),
+        TermKeyCode::Right     => Some(KeyCode::Right),
+        TermKeyCode::Up        => Some(KeyCode::Up),
+        TermKeyCode::Down      => Some(KeyCode::Down),
+        TermKeyCode::Enter     => Some(KeyCode::Enter),
+        TermKeyCode::Char(' ') => Some(KeyCode::Space),
+        TermKeyCode::Char('/') => Some(KeyCode::Slash),
+        TermKeyCode::Char(ch)  => match ch.to_ascii_lowercase() {
+            'w' => Some(KeyCode::W),
+            's' => Some(KeyCode::S),
+            'a' => Some(KeyCode::A),
+            'c' => Some(KeyCode::C),
+            'q' => Some(KeyCode::Q),
+            _   => None,
+        },
+        _                      => None,
+    }
+}
+
+fn board_lines(board: &Board, theme: &Theme) -> Vec<String> {
+    let view = board.visible_view();
+    let mut ghost_rows = vec![vec![false; board.num_cols()]; view.len()];
+    if board.ruleset().ghost_enabled {
+        for pos in board.ghost_cursor_positions() {
+            let (Ok(y), Ok(x)) = (
+                usize::try_from(pos.y).map(|y| y.checked_sub(board.num_hidden_rows())),
+                usize::try_from(pos.x),
+            ) else {
+                continue;
+            };
+            if let Some(ghost) = y.and_then(|y| ghost_rows.get_mut(y)?.get_mut(x)) {
+                *ghost = true;
+            }
+        }
+    }
+
+    let mut lines: Vec<String> = view
+        .iter()
+        .zip(&ghost_rows)
+        .map(|(row, ghost_row)| {
+            let cells: String = row
+                .iter()
+                .zip(ghost_row)
+                .map(|(&view_cell, &ghost)| cell_text(view_cell, ghost, theme))
+                .collect();
+            format!("│{cells}│")
+        })
+        .collect();
+    lines.push(format!("└{}┘", "─".repeat(2 * board.num_cols())));
+    lines
+}
+
+fn cell_text(view_cell: ViewCell, ghost: bool, theme: &Theme) -> String {
+    match view_cell {
+        ViewCell::Empty if ghost => colored(GHOST_CELL, theme.ghost),
+        ViewCell::Empty => String::from(EMPTY_CELL),
+        ViewCell::Active(shape) => block_text(shape, theme.active(shape), theme),
+        ViewCell::Stack(shape) => block_text(shape, theme.shape(shape), theme),
+        ViewCell::Garbage => colored(BLOCK_CELL, theme.garbage.fill),
+    }
+}
+
+// With patterns on, a piece's cells show its shape's letter, since a terminal
+// can't draw the window's marks.
+fn block_text(shape: Shape, colors: CellColors, theme: &Theme) -> String {
+    if !theme.patterns {
+        return colored(BLOCK_CELL, colors.fill);
+    }
+    format!(
+        "{}{}{}{}",
+        SetBackgroundColor(to_terminal_color(colors.fill)),
+        SetForegroundColor(to_terminal_color(colors.outline)),
+        Print(format!("{} ", shape_letter(shape))),
+        ResetColor
+    )
+}
+
+fn shape_letter(shape: Shape) -> char {
+    match shape {
+        Shape::I => 'I',
+        Shape::O => 'O',
+        Shape::T => 'T',
+        Shape::S => 'S',
+        Shape::Z => 'Z',
+        Shape::J => 'J',
+        Shape::L => 'L',
+    }
+}
+
+fn side_panel_lines(board: &Board, hud: &Hud, theme: &Theme) -> Vec<String> {
+    let mut lines = Vec::new();
+    let next_pieces: Vec<&Piece> = board.next_pieces(board.ruleset().num_next_pieces).collect();
+    if let Some((next_piece, queued_pieces)) = next_pieces.split_first() {
+        lines.push(String::from("Next"));
+        lines.extend(preview_lines(
+            next_piece,
+            theme.active(next_piece.shape()),
+            theme,
+        ));
+        // A terminal can't draw smaller pieces, so the rest of the queue is laid
+        // out two to a row instead.
+        for queued_pair in queued_pieces.chunks(2) {
+            lines.push(String::new());
+            let pair_lines = queued_pair
+                .iter()
+                .map(|piece| preview_lines(piece, theme.active(piece.shape()), theme));
+            let mut row_lines = vec![String::new(); 2];
+            for (piece_idx, piece_lines) in pair_lines.enumerate() {
+                for (row_line, piece_line) in row_lines.iter_mut().zip(piece_lines) {
+                    if piece_idx > 0 {
+                        row_line.push_str("  ");
+                    }
+                    row_line.push_str(&piece_line);
+                }
+            }
+            lines.extend(row_lines);
+        }
+        lines.push(String::new());
+    }
+
+    if board.ruleset().hold_enabled {
+        lines.push(String::from("Hold"));
+        match board.held_piece() {
+            // Greyed out while the piece in play has already been swapped.
+            Some(held_piece) if board.hold_used() => {
+                lines.extend(preview_lines(&held_piece, theme.garbage, theme));
+            }
+            Some(held_piece) => {
+                let colors = theme.active(held_piece.shape());
+                lines.extend(preview_lines(&held_piece, colors, theme));
+            }
+            None => lines.extend([String::new(), String::new()]),
+        }
+        lines.push(String::new());
+    }
+
+    for (label, value) in hud.entries() {
+        lines.push(format!(
+            "{label:<HUD_LABEL_WIDTH$}{value:>HUD_VALUE_WIDTH$}"
+        ));
+    }
+    if !hud.status_lines.is_empty() {
+        lines.push(String::new());
+        lines.extend_from_slice(&hud.status_lines);
+    }
+    if let Some(streak_text) = hud.streak_text() {
+        lines.extend([String::new(), streak_text]);
+    }
+    lines
+}
+
+// A piece on two rows, in its spawn orientation, centered across the width of
+// the widest piece.
+fn preview_lines(piece: &Piece, colors: CellColors, theme: &Theme) -> Vec<String> {
+    let points = piece.get_local_points();
+    let min_x = points.iter().map(|pos| pos.x).min().unwrap_or(0);
+    let max_x = points.iter().map(|pos| pos.x).max().unwrap_or(0);
+    let min_y = points.iter().map(|pos| pos.y).min().unwrap_or(0);
+    let piece_width = usize::try_from(max_x - min_x + 1).unwrap_or(PREVIEW_WIDTH);
+    let mut rows = vec![vec![false; piece_width]; 2];
+    for pos in points {
+        let (Ok(x), Ok(y)) = (
+            usize::try_from(pos.x - min_x),
+            usize::try_from(pos.y - min_y),
+        ) else {
+            continue;
+        };
+        if let Some(filled) = rows.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *filled = true;
+        }
+    }
+    // Cells are two characters wide, so half a cell of margin is one character.
+    let margin = PREVIEW_WIDTH.saturating_sub(piece_width);
+    rows.iter()
+        .map(|row| {
+            let cells: String = row
+                .iter()
+                .map(|&filled| {
+                    if filled {
+                        block_text(piece.shape(), colors, theme)
+                    } else {
+                        String::from("  ")
+                    }
+                })
+                .collect();
+            format!("{}{cells}{}", " ".repeat(margin), " ".repeat(margin))
+        })
+        .collect()
+}
+
+fn colored(text: &str, color: Color) -> String {
+    format!(
+        "{}{}{}",
+        SetForegroundColor(to_terminal_color(color)),
+        Print(text),
+        ResetColor
+    )
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_terminal_color(color: Color) -> style::Color {
+    let [r, g, b] = [color.r, color.g, color.b].map(|channel| (channel * 255.).round() as u8);
+    style::Color::Rgb { r, g, b }
+}
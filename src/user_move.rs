@@ -2,8 +2,14 @@
 pub enum UserMove {
     SoftDown,
     HardDown,
+    // Drops to the ghost position without locking.
+    SonicDrop,
+    // A sonic drop that also restarts the lock delay, once per piece.
+    FirmDrop,
     Left,
     Right,
     RotateCW,
     RotateCCW,
+    Rotate180,
+    Hold,
 }